@@ -0,0 +1,219 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec3 FragPos;
+in vec3 Normal;
+in vec2 TexCoord;
+in vec4 Tangent;
+
+// Must match `MAX_LIGHTS` in `wrappers::lighting`.
+#define MAX_LIGHTS 16
+// Must match `MAX_SHADOW_MAPS` in `wrappers::shadows`.
+#define MAX_SHADOW_MAPS 8
+
+#define DIRECTIONAL 0
+#define POINT 1
+#define SPOT 2
+
+struct Light {
+    // w is the light kind
+    vec4 position;
+    vec4 direction;
+    // rgb premultiplied by intensity
+    vec4 color;
+    // constant, linear, quadratic
+    vec4 attenuation;
+    // cosines of the inner and outer angle
+    vec4 cone;
+    // first shadow map layer (-1 without shadows), layer count, bias, normal bias
+    vec4 shadow;
+};
+
+layout (std140) uniform Camera {
+    mat4 projection;
+    mat4 view;
+    vec3 cameraPos;
+};
+
+layout (std140) uniform Lights {
+    vec4 ambient;
+    int lightCount;
+    Light lights[MAX_LIGHTS];
+};
+
+layout (std140) uniform Shadows {
+    mat4 lightSpace[MAX_SHADOW_MAPS];
+    // x is the view depth a cascade ends at
+    vec4 cascadeSplits[MAX_SHADOW_MAPS];
+    // x is the PCF radius in texels
+    vec4 shadowParams;
+};
+
+uniform sampler2DArrayShadow shadowMaps;
+
+uniform sampler2D diffuseMap;
+uniform vec4 diffuseColor;
+// fragments with less alpha are discarded, 0 keeps everything
+uniform float alphaCutoff;
+uniform float specularStrength;
+uniform float shininess;
+uniform sampler2D normalMap;
+uniform float normalScale;
+// r is the height, white being the surface and black the deepest point
+uniform sampler2D heightMap;
+uniform float heightScale;
+
+// Vertex tangents when the mesh has them, otherwise a frame from screen-space derivatives.
+mat3 tangentFrame(vec3 normal)
+{
+    if (dot(Tangent.xyz, Tangent.xyz) > 0.0) {
+        vec3 tangent = normalize(Tangent.xyz - normal * dot(normal, Tangent.xyz));
+        vec3 bitangent = cross(normal, tangent) * (Tangent.w < 0.0 ? -1.0 : 1.0);
+        return mat3(tangent, bitangent, normal);
+    }
+
+    vec3 dp1 = dFdx(FragPos);
+    vec3 dp2 = dFdy(FragPos);
+    vec2 duv1 = dFdx(TexCoord);
+    vec2 duv2 = dFdy(TexCoord);
+
+    vec3 dp2perp = cross(dp2, normal);
+    vec3 dp1perp = cross(normal, dp1);
+    vec3 tangent = dp2perp * duv1.x + dp1perp * duv2.x;
+    vec3 bitangent = dp2perp * duv1.y + dp1perp * duv2.y;
+    float invmax = inversesqrt(max(dot(tangent, tangent), dot(bitangent, bitangent)));
+    if (isinf(invmax) || isnan(invmax)) {
+        return mat3(vec3(0.0), vec3(0.0), normal);
+    }
+
+    return mat3(tangent * invmax, bitangent * invmax, normal);
+}
+
+vec3 perturbNormal(mat3 tbn, vec2 uv)
+{
+    vec3 sampled = texture(normalMap, uv).xyz * 2.0 - 1.0;
+    sampled.xy *= normalScale;
+    return normalize(tbn * sampled);
+}
+
+// Steep parallax search through the height map, refined by interpolating the last two layers.
+// `viewDir` is in tangent space.
+vec2 parallaxOcclusion(vec2 uv, vec3 viewDir)
+{
+    if (heightScale <= 0.0) {
+        return uv;
+    }
+
+    const float minLayers = 8.0;
+    const float maxLayers = 32.0;
+    float layers = mix(maxLayers, minLayers, abs(viewDir.z));
+    float layerDepth = 1.0 / layers;
+    vec2 delta = viewDir.xy / max(viewDir.z, 0.05) * heightScale / layers;
+
+    // Explicit gradients, implicit ones are undefined inside non-uniform loops.
+    vec2 dx = dFdx(uv);
+    vec2 dy = dFdy(uv);
+
+    vec2 coords = uv;
+    float depth = 1.0 - textureGrad(heightMap, coords, dx, dy).r;
+    float current = 0.0;
+    for (int i = 0; i < int(maxLayers) && current < depth; i++) {
+        coords -= delta;
+        depth = 1.0 - textureGrad(heightMap, coords, dx, dy).r;
+        current += layerDepth;
+    }
+
+    vec2 previous = coords + delta;
+    float after = depth - current;
+    float before = 1.0 - textureGrad(heightMap, previous, dx, dy).r - current + layerDepth;
+    float weight = after / (after - before);
+    return mix(coords, previous, clamp(weight, 0.0, 1.0));
+}
+
+// Fraction of `light` reaching the fragment, averaged over a PCF kernel.
+float shadowFactor(Light light, vec3 normal)
+{
+    int layer = int(light.shadow.x);
+    if (layer < 0) {
+        return 1.0;
+    }
+
+    if (int(light.position.w) == DIRECTIONAL) {
+        float depth = -(view * vec4(FragPos, 1.0)).z;
+        int last = layer + int(light.shadow.y) - 1;
+        if (depth > cascadeSplits[last].x) {
+            return 1.0;
+        }
+        while (layer < last && depth > cascadeSplits[layer].x) {
+            layer++;
+        }
+    }
+
+    vec4 lightPos = lightSpace[layer] * vec4(FragPos + normal * light.shadow.w, 1.0);
+    vec3 coords = lightPos.xyz / lightPos.w * 0.5 + 0.5;
+    if (coords.z > 1.0) {
+        return 1.0;
+    }
+
+    int radius = int(shadowParams.x);
+    vec2 texel = 1.0 / vec2(textureSize(shadowMaps, 0).xy);
+    float lit = 0.0;
+    for (int x = -radius; x <= radius; x++) {
+        for (int y = -radius; y <= radius; y++) {
+            vec2 uv = coords.xy + vec2(x, y) * texel;
+            lit += texture(shadowMaps, vec4(uv, float(layer), coords.z - light.shadow.z));
+        }
+    }
+
+    float kernel = float(2 * radius + 1);
+    return lit / (kernel * kernel);
+}
+
+void main()
+{
+    vec3 geometricNormal = normalize(Normal);
+    mat3 tbn = tangentFrame(geometricNormal);
+    vec3 viewDir = normalize(cameraPos - FragPos);
+    vec2 uv = parallaxOcclusion(TexCoord, normalize(transpose(tbn) * viewDir));
+
+    vec4 albedo = texture(diffuseMap, uv) * diffuseColor;
+    vec3 normal = perturbNormal(tbn, uv);
+    if (albedo.a < alphaCutoff) {
+        discard;
+    }
+
+    vec3 color = ambient.rgb * albedo.rgb;
+    for (int i = 0; i < lightCount; i++) {
+        Light light = lights[i];
+        int kind = int(light.position.w);
+
+        vec3 lightDir;
+        float attenuation = 1.0;
+        if (kind == DIRECTIONAL) {
+            lightDir = normalize(-light.direction.xyz);
+        } else {
+            vec3 toLight = light.position.xyz - FragPos;
+            float dist = length(toLight);
+            lightDir = toLight / dist;
+            attenuation = 1.0 / (light.attenuation.x + light.attenuation.y * dist
+                               + light.attenuation.z * dist * dist);
+
+            if (kind == SPOT) {
+                float theta = dot(lightDir, normalize(-light.direction.xyz));
+                attenuation *= clamp((theta - light.cone.y) / (light.cone.x - light.cone.y), 0.0, 1.0);
+            }
+        }
+
+        float diffuse = max(dot(normal, lightDir), 0.0);
+        vec3 halfway = normalize(lightDir + viewDir);
+        float specular = diffuse > 0.0
+            ? pow(max(dot(normal, halfway), 0.0), shininess) * specularStrength
+            : 0.0;
+
+        attenuation *= shadowFactor(light, geometricNormal);
+
+        color += (albedo.rgb * diffuse + specular) * light.color.rgb * attenuation;
+    }
+
+    FragColor = vec4(color, albedo.a);
+}
@@ -0,0 +1,31 @@
+#version 330 core
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec2 aTexCoord;
+layout (location = 2) in vec3 aNormal;
+layout (location = 3) in vec4 aTangent;
+// One per instance, takes locations 4 to 7
+layout (location = 4) in mat4 instanceModel;
+
+out vec3 FragPos;
+out vec3 Normal;
+out vec2 TexCoord;
+// w is the handedness, all zero when the mesh has no tangents
+out vec4 Tangent;
+
+layout (std140) uniform Camera {
+    mat4 projection;
+    mat4 view;
+    vec3 cameraPos;
+};
+
+void main() {
+    mat4 model = instanceModel;
+    vec4 worldPos = model * vec4(aPos, 1.0);
+    FragPos = worldPos.xyz;
+    // The inverse transpose keeps normals perpendicular under non-uniform scale.
+    Normal = mat3(transpose(inverse(model))) * aNormal;
+    TexCoord = aTexCoord;
+    // Tangents lie in the surface, so they transform like positions.
+    Tangent = vec4(mat3(model) * aTangent.xyz, aTangent.w);
+    gl_Position = projection * view * worldPos;
+}
@@ -0,0 +1,247 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec3 FragPos;
+in vec3 Normal;
+in vec2 TexCoord;
+in vec4 Tangent;
+
+// Must match `MAX_LIGHTS` in `wrappers::lighting`.
+#define MAX_LIGHTS 16
+// Must match `MAX_SHADOW_MAPS` in `wrappers::shadows`.
+#define MAX_SHADOW_MAPS 8
+// Must match `PREFILTERED_LEVELS` in `wrappers::ibl`.
+#define PREFILTERED_LEVELS 5
+
+#define DIRECTIONAL 0
+#define POINT 1
+#define SPOT 2
+
+const float PI = 3.14159265359;
+
+struct Light {
+    // w is the light kind
+    vec4 position;
+    vec4 direction;
+    // rgb premultiplied by intensity
+    vec4 color;
+    // constant, linear, quadratic
+    vec4 attenuation;
+    // cosines of the inner and outer angle
+    vec4 cone;
+    // first shadow map layer (-1 without shadows), layer count, bias, normal bias
+    vec4 shadow;
+};
+
+layout (std140) uniform Camera {
+    mat4 projection;
+    mat4 view;
+    vec3 cameraPos;
+};
+
+layout (std140) uniform Lights {
+    vec4 ambient;
+    int lightCount;
+    Light lights[MAX_LIGHTS];
+};
+
+layout (std140) uniform Shadows {
+    mat4 lightSpace[MAX_SHADOW_MAPS];
+    // x is the view depth a cascade ends at
+    vec4 cascadeSplits[MAX_SHADOW_MAPS];
+    // x is the PCF radius in texels
+    vec4 shadowParams;
+};
+
+uniform sampler2DArrayShadow shadowMaps;
+
+uniform sampler2D albedoMap;
+// g is roughness, b is metallic
+uniform sampler2D metallicRoughnessMap;
+uniform sampler2D aoMap;
+uniform sampler2D emissiveMap;
+uniform sampler2D normalMap;
+
+uniform samplerCube irradianceMap;
+uniform samplerCube prefilteredMap;
+uniform sampler2D brdfLUT;
+
+uniform vec4 albedoFactor;
+uniform float metallicFactor;
+uniform float roughnessFactor;
+uniform float aoStrength;
+uniform float normalScale;
+uniform vec4 emissiveFactor;
+// fragments with less alpha are discarded, 0 keeps everything
+uniform float alphaCutoff;
+
+// Fraction of `light` reaching the fragment, averaged over a PCF kernel.
+float shadowFactor(Light light, vec3 normal)
+{
+    int layer = int(light.shadow.x);
+    if (layer < 0) {
+        return 1.0;
+    }
+
+    if (int(light.position.w) == DIRECTIONAL) {
+        float depth = -(view * vec4(FragPos, 1.0)).z;
+        int last = layer + int(light.shadow.y) - 1;
+        if (depth > cascadeSplits[last].x) {
+            return 1.0;
+        }
+        while (layer < last && depth > cascadeSplits[layer].x) {
+            layer++;
+        }
+    }
+
+    vec4 lightPos = lightSpace[layer] * vec4(FragPos + normal * light.shadow.w, 1.0);
+    vec3 coords = lightPos.xyz / lightPos.w * 0.5 + 0.5;
+    if (coords.z > 1.0) {
+        return 1.0;
+    }
+
+    int radius = int(shadowParams.x);
+    vec2 texel = 1.0 / vec2(textureSize(shadowMaps, 0).xy);
+    float lit = 0.0;
+    for (int x = -radius; x <= radius; x++) {
+        for (int y = -radius; y <= radius; y++) {
+            vec2 uv = coords.xy + vec2(x, y) * texel;
+            lit += texture(shadowMaps, vec4(uv, float(layer), coords.z - light.shadow.z));
+        }
+    }
+
+    float kernel = float(2 * radius + 1);
+    return lit / (kernel * kernel);
+}
+
+// Vertex tangents when the mesh has them, otherwise a frame from screen-space derivatives.
+mat3 tangentFrame(vec3 normal)
+{
+    if (dot(Tangent.xyz, Tangent.xyz) > 0.0) {
+        vec3 tangent = normalize(Tangent.xyz - normal * dot(normal, Tangent.xyz));
+        vec3 bitangent = cross(normal, tangent) * (Tangent.w < 0.0 ? -1.0 : 1.0);
+        return mat3(tangent, bitangent, normal);
+    }
+
+    vec3 dp1 = dFdx(FragPos);
+    vec3 dp2 = dFdy(FragPos);
+    vec2 duv1 = dFdx(TexCoord);
+    vec2 duv2 = dFdy(TexCoord);
+
+    vec3 dp2perp = cross(dp2, normal);
+    vec3 dp1perp = cross(normal, dp1);
+    vec3 tangent = dp2perp * duv1.x + dp1perp * duv2.x;
+    vec3 bitangent = dp2perp * duv1.y + dp1perp * duv2.y;
+    float invmax = inversesqrt(max(dot(tangent, tangent), dot(bitangent, bitangent)));
+    if (isinf(invmax) || isnan(invmax)) {
+        return mat3(vec3(0.0), vec3(0.0), normal);
+    }
+
+    return mat3(tangent * invmax, bitangent * invmax, normal);
+}
+
+vec3 perturbNormal(mat3 tbn, vec2 uv)
+{
+    vec3 sampled = texture(normalMap, uv).xyz * 2.0 - 1.0;
+    sampled.xy *= normalScale;
+    return normalize(tbn * sampled);
+}
+
+float distributionGGX(float NdotH, float roughness)
+{
+    float a2 = roughness * roughness * roughness * roughness;
+    float denom = NdotH * NdotH * (a2 - 1.0) + 1.0;
+    return a2 / (PI * denom * denom);
+}
+
+float geometrySmith(float NdotV, float NdotL, float roughness)
+{
+    float r = roughness + 1.0;
+    float k = r * r / 8.0;
+    float ggxV = NdotV / (NdotV * (1.0 - k) + k);
+    float ggxL = NdotL / (NdotL * (1.0 - k) + k);
+    return ggxV * ggxL;
+}
+
+vec3 fresnelSchlick(float cosTheta, vec3 F0)
+{
+    return F0 + (1.0 - F0) * pow(clamp(1.0 - cosTheta, 0.0, 1.0), 5.0);
+}
+
+vec3 fresnelSchlickRoughness(float cosTheta, vec3 F0, float roughness)
+{
+    return F0 + (max(vec3(1.0 - roughness), F0) - F0) * pow(clamp(1.0 - cosTheta, 0.0, 1.0), 5.0);
+}
+
+void main()
+{
+    vec4 albedo = texture(albedoMap, TexCoord) * albedoFactor;
+    vec4 metallicRoughness = texture(metallicRoughnessMap, TexCoord);
+    float roughness = clamp(metallicRoughness.g * roughnessFactor, 0.04, 1.0);
+    float metallic = clamp(metallicRoughness.b * metallicFactor, 0.0, 1.0);
+    float ao = mix(1.0, texture(aoMap, TexCoord).r, aoStrength);
+    vec3 emissive = texture(emissiveMap, TexCoord).rgb * emissiveFactor.rgb;
+
+    vec3 normal = perturbNormal(tangentFrame(normalize(Normal)), TexCoord);
+    // After the derivatives, which are undefined once neighbouring fragments are discarded.
+    if (albedo.a < alphaCutoff) {
+        discard;
+    }
+    vec3 viewDir = normalize(cameraPos - FragPos);
+    float NdotV = max(dot(normal, viewDir), 1e-4);
+
+    // Dielectrics reflect about 4% at normal incidence, metals tint reflections with albedo.
+    vec3 F0 = mix(vec3(0.04), albedo.rgb, metallic);
+
+    vec3 color = vec3(0.0);
+    for (int i = 0; i < lightCount; i++) {
+        Light light = lights[i];
+        int kind = int(light.position.w);
+
+        vec3 lightDir;
+        float attenuation = 1.0;
+        if (kind == DIRECTIONAL) {
+            lightDir = normalize(-light.direction.xyz);
+        } else {
+            vec3 toLight = light.position.xyz - FragPos;
+            float dist = length(toLight);
+            lightDir = toLight / dist;
+            attenuation = 1.0 / (light.attenuation.x + light.attenuation.y * dist
+                               + light.attenuation.z * dist * dist);
+
+            if (kind == SPOT) {
+                float theta = dot(lightDir, normalize(-light.direction.xyz));
+                attenuation *= clamp((theta - light.cone.y) / (light.cone.x - light.cone.y), 0.0, 1.0);
+            }
+        }
+
+        float NdotL = max(dot(normal, lightDir), 0.0);
+        if (NdotL == 0.0) {
+            continue;
+        }
+
+        attenuation *= shadowFactor(light, normalize(Normal));
+
+        vec3 halfway = normalize(lightDir + viewDir);
+        float NdotH = max(dot(normal, halfway), 0.0);
+        vec3 F = fresnelSchlick(max(dot(halfway, viewDir), 0.0), F0);
+        vec3 specular = distributionGGX(NdotH, roughness) * geometrySmith(NdotV, NdotL, roughness) * F
+                      / (4.0 * NdotV * NdotL + 1e-4);
+        vec3 diffuse = (1.0 - F) * (1.0 - metallic) * albedo.rgb / PI;
+
+        color += (diffuse + specular) * light.color.rgb * attenuation * NdotL;
+    }
+
+    // Image-based lighting, split-sum approximation for the specular part.
+    vec3 F = fresnelSchlickRoughness(NdotV, F0, roughness);
+    vec3 diffuseIbl = texture(irradianceMap, normal).rgb * albedo.rgb * (1.0 - F) * (1.0 - metallic);
+    vec3 reflected = reflect(-viewDir, normal);
+    vec3 prefiltered = textureLod(prefilteredMap, reflected, roughness * float(PREFILTERED_LEVELS - 1)).rgb;
+    vec2 brdf = texture(brdfLUT, vec2(NdotV, roughness)).rg;
+    vec3 specularIbl = prefiltered * (F * brdf.x + brdf.y);
+
+    color += (diffuseIbl + specularIbl + ambient.rgb * albedo.rgb) * ao;
+    color += emissive;
+
+    FragColor = vec4(color, albedo.a);
+}
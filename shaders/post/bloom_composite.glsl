@@ -0,0 +1,14 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoord;
+
+uniform sampler2D screen;
+uniform sampler2D bloom;
+uniform float intensity;
+
+void main()
+{
+    vec4 color = texture(screen, TexCoord);
+    FragColor = vec4(color.rgb + texture(bloom, TexCoord).rgb * intensity, color.a);
+}
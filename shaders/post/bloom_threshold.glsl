@@ -0,0 +1,15 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoord;
+
+uniform sampler2D screen;
+uniform float threshold;
+
+void main()
+{
+    vec3 color = texture(screen, TexCoord).rgb;
+    float brightness = max(color.r, max(color.g, color.b));
+    float contribution = max(brightness - threshold, 0.0) / max(brightness, 1e-4);
+    FragColor = vec4(color * contribution, 1.0);
+}
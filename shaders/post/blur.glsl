@@ -0,0 +1,21 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoord;
+
+uniform sampler2D screen;
+// (1, 0) for the horizontal pass, (0, 1) for the vertical one
+uniform vec4 direction;
+
+const float weights[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+void main()
+{
+    vec2 offset = direction.xy / vec2(textureSize(screen, 0));
+    vec3 color = texture(screen, TexCoord).rgb * weights[0];
+    for (int i = 1; i < 5; i++) {
+        color += texture(screen, TexCoord + offset * float(i)).rgb * weights[i];
+        color += texture(screen, TexCoord - offset * float(i)).rgb * weights[i];
+    }
+    FragColor = vec4(color, 1.0);
+}
@@ -0,0 +1,18 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoord;
+
+uniform sampler2D screen;
+uniform sampler3D lut;
+uniform float intensity;
+
+void main()
+{
+    vec4 color = texture(screen, TexCoord);
+    // Sample texel centers so the LUT edges map exactly to 0 and 1.
+    float size = float(textureSize(lut, 0).x);
+    vec3 coord = clamp(color.rgb, 0.0, 1.0) * ((size - 1.0) / size) + 0.5 / size;
+    vec3 graded = texture(lut, coord).rgb;
+    FragColor = vec4(mix(color.rgb, graded, intensity), color.a);
+}
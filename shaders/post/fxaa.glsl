@@ -0,0 +1,44 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoord;
+
+uniform sampler2D screen;
+
+const float SPAN_MAX = 8.0;
+const float REDUCE_MUL = 1.0 / 8.0;
+const float REDUCE_MIN = 1.0 / 128.0;
+
+float luma(vec3 color)
+{
+    return dot(color, vec3(0.299, 0.587, 0.114));
+}
+
+// Expects tonemapped input, run it after `Tonemap`.
+void main()
+{
+    vec2 texel = 1.0 / vec2(textureSize(screen, 0));
+
+    float nw = luma(texture(screen, TexCoord + vec2(-1.0, -1.0) * texel).rgb);
+    float ne = luma(texture(screen, TexCoord + vec2(1.0, -1.0) * texel).rgb);
+    float sw = luma(texture(screen, TexCoord + vec2(-1.0, 1.0) * texel).rgb);
+    float se = luma(texture(screen, TexCoord + vec2(1.0, 1.0) * texel).rgb);
+    vec4 center = texture(screen, TexCoord);
+    float m = luma(center.rgb);
+
+    float lumaMin = min(m, min(min(nw, ne), min(sw, se)));
+    float lumaMax = max(m, max(max(nw, ne), max(sw, se)));
+
+    vec2 dir = vec2(-((nw + ne) - (sw + se)), (nw + sw) - (ne + se));
+    float reduce = max((nw + ne + sw + se) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+    float scale = 1.0 / (min(abs(dir.x), abs(dir.y)) + reduce);
+    dir = clamp(dir * scale, vec2(-SPAN_MAX), vec2(SPAN_MAX)) * texel;
+
+    vec3 a = 0.5 * (texture(screen, TexCoord + dir * (1.0 / 3.0 - 0.5)).rgb
+                  + texture(screen, TexCoord + dir * (2.0 / 3.0 - 0.5)).rgb);
+    vec3 b = a * 0.5 + 0.25 * (texture(screen, TexCoord - dir * 0.5).rgb
+                             + texture(screen, TexCoord + dir * 0.5).rgb);
+
+    float lumaB = luma(b);
+    FragColor = vec4(lumaB < lumaMin || lumaB > lumaMax ? a : b, center.a);
+}
@@ -0,0 +1,27 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoord;
+
+uniform sampler2D screen;
+uniform float exposure;
+// 0 - Reinhard, 1 - ACES filmic fit
+uniform int operator;
+
+vec3 aces(vec3 x)
+{
+    const float a = 2.51;
+    const float b = 0.03;
+    const float c = 2.43;
+    const float d = 0.59;
+    const float e = 0.14;
+    return clamp((x * (a * x + b)) / (x * (c * x + d) + e), 0.0, 1.0);
+}
+
+void main()
+{
+    vec4 hdr = texture(screen, TexCoord);
+    vec3 color = hdr.rgb * exposure;
+    color = operator == 1 ? aces(color) : color / (color + vec3(1.0));
+    FragColor = vec4(color, hdr.a);
+}
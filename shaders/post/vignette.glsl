@@ -0,0 +1,16 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoord;
+
+uniform sampler2D screen;
+uniform float intensity;
+uniform float smoothness;
+
+void main()
+{
+    vec4 color = texture(screen, TexCoord);
+    float dist = length(TexCoord - vec2(0.5)) * sqrt(2.0);
+    float shade = 1.0 - intensity * smoothstep(1.0 - smoothness, 1.0, dist);
+    FragColor = vec4(color.rgb * shade, color.a);
+}
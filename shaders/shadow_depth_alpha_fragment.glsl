@@ -0,0 +1,13 @@
+#version 330 core
+in vec2 TexCoord;
+
+uniform sampler2D alphaMap;
+uniform float alphaCutoff;
+
+// Depth is written by the fixed-function stage, for the fragments that pass the test.
+void main()
+{
+    if (texture(alphaMap, TexCoord).a < alphaCutoff) {
+        discard;
+    }
+}
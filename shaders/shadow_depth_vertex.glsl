@@ -0,0 +1,14 @@
+#version 330 core
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec2 aTexCoord;
+layout (location = 4) in mat4 instanceModel;
+
+out vec2 TexCoord;
+
+uniform mat4 lightSpace;
+
+void main()
+{
+    TexCoord = aTexCoord;
+    gl_Position = lightSpace * instanceModel * vec4(aPos, 1.0);
+}
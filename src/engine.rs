@@ -8,15 +8,37 @@ use std::{
 use crate::{
     object::{Object, ObjectConstructor},
     wrappers::{
+        buffer_object::BufferObject,
+        compressed::BuilderCompressedTexture2D,
+        culling::Frustum,
+        framebuffer::{self, BuilderFramebuffer, Framebuffer},
         gl::GL,
+        ibl::{self, IblBaker},
+        lighting::{self, LightsBlock},
+        material::{BlendMode, Material},
         mesh::{BoundStaticMesh, Mesh},
-        textures::{BuilderTexture2D, Texture2D},
-        types::Vec4,
+        post_process::{PostEffect, PostProcess},
+        render_queue::{DrawItem, RenderQueue, RenderStats, StateCache},
+        shadows::{self, CameraView, ShadowMaps, ShadowQuality, ShadowView, ShadowsBlock},
+        skybox::Skybox,
+        textures::{
+            self, BuilderTexture2D, BuilderTexture2DArray, BuilderTexture3D, BuilderTextureCube,
+            Texture, Texture2D, TextureCube,
+        },
+        types::{ColorSpace, TextureUnit, Vec3, Vec4},
+        uniform_buffer::{
+            CameraBlock, UniformBlock, UniformBuffer, CAMERA_BLOCK_BINDING, LIGHTS_BLOCK_BINDING,
+            SHADOWS_BLOCK_BINDING,
+        },
     },
 };
 use egui_glfw::EguiBackend;
-use engine_math::{transform::homogeneous::perspective3, Matrix4};
+use engine_math::{
+    transform::homogeneous::{lookat3, perspective3},
+    Matrix4, Vector, Vector3,
+};
 use glfw::{Action, Context, Glfw, Key, SwapInterval, Window, WindowEvent};
+use image::DynamicImage;
 
 use crate::wrappers::{
     shader::{Shader, ShaderSource},
@@ -24,26 +46,65 @@ use crate::wrappers::{
 };
 
 pub type Meshes = HashMap<String, BoundStaticMesh>;
-pub type Textures = HashMap<String, Texture2D>;
+pub type Textures = HashMap<String, Box<dyn Texture>>;
+pub type Shaders = HashMap<String, Shader>;
+pub type Materials = HashMap<String, Material>;
+pub type Framebuffers = HashMap<String, Framebuffer>;
+
+/// Name under which the shader passed to [`UnsafeEngine::new`] is registered.
+pub const DEFAULT_SHADER: &str = "default";
+/// Name of the built-in Blinn-Phong shader, see [`Material::lit`].
+pub const LIT_SHADER: &str = "lit";
+/// Name of the built-in metallic-roughness shader, see [`PbrMaterial`](crate::wrappers::material::PbrMaterial).
+pub const PBR_SHADER: &str = "pbr";
+/// Name of the built-in 1x1 white texture.
+pub const WHITE_TEXTURE: &str = "white";
+/// Vertical field of view of the camera, in degrees.
+const CAMERA_FOV: f32 = 45.;
+const CAMERA_NEAR: f32 = 0.01;
+const CAMERA_FAR: f32 = 10000.;
+
+/// Name of the built-in 1x1 tangent-space normal map pointing straight out of the surface.
+pub const FLAT_NORMAL_TEXTURE: &str = "flat_normal";
 
 pub struct UnsafeEngine {
-    shader: Shader,
+    shaders: Shaders,
     objects: Vec<Object>,
     commands: RefCell<Vec<Command>>,
     meshes: Meshes,
     textures: Textures,
+    missing_texture: Texture2D,
+    materials: Materials,
+    framebuffers: Framebuffers,
+    render_target: Option<String>,
+    post_process: Option<PostProcess>,
+    skybox: Option<Skybox>,
+    ibl_baker: Option<IblBaker>,
+    shadow_maps: ShadowMaps,
+    shadow_quality: ShadowQuality,
     _gl: GL,
     window: Window,
     reciever: Receiver<(f64, WindowEvent)>,
     glfw: Glfw,
     time_diff: Duration,
     projection: Matrix4,
+    view: Matrix4,
+    camera_position: Vec3,
+    camera_target: Vec3,
+    camera_block: UniformBuffer<CameraBlock>,
+    lights_block: UniformBuffer<LightsBlock>,
+    shadows_block: UniformBuffer<ShadowsBlock>,
+    ambient_light: Vec3,
+    material_block: BufferObject,
+    srgb_framebuffer: bool,
+    render_stats: RenderStats,
     egui: EguiBackend,
 }
 
 impl UnsafeEngine {
     pub fn new(shader: ShaderSource) -> UnsafeEngine {
         let mut glfw = glfw::init(glfw::fail_on_errors).unwrap();
+        glfw.window_hint(glfw::WindowHint::SRgbCapable(true));
 
         let (mut window, reciever) = glfw
             .create_window(800, 600, "Я илюша обухов", glfw::WindowMode::Windowed)
@@ -68,8 +129,50 @@ impl UnsafeEngine {
             gl::Enable(gl::DEPTH_TEST);
         }
 
+        let shader = shader.compile(&_gl);
+        bind_engine_blocks(&shader);
+        let missing_texture = Texture2D::missing("missing").process(&shader);
+        let white_texture =
+            Texture2D::solid_color(Vec4::new(1., 1., 1., 1.), WHITE_TEXTURE).process(&shader);
+        let flat_normal_texture =
+            Texture2D::solid_color(Vec4::new(0.5, 0.5, 1., 1.), FLAT_NORMAL_TEXTURE)
+                .set_color_space(ColorSpace::Linear)
+                .process(&shader);
+        // Black until an environment is set, leaving PBR materials lit by light sources only.
+        let brdf_lut = Texture2D::solid_color(Vec4::new(0., 0., 0., 1.), ibl::BRDF_LUT)
+            .set_color_space(ColorSpace::Linear)
+            .process(&shader);
+        let mut textures: Textures = HashMap::new();
+        textures.insert(WHITE_TEXTURE.to_string(), Box::new(white_texture));
+        textures.insert(
+            FLAT_NORMAL_TEXTURE.to_string(),
+            Box::new(flat_normal_texture),
+        );
+        textures.insert(ibl::BRDF_LUT.to_string(), Box::new(brdf_lut));
+        textures.insert(ibl::IRRADIANCE_MAP.to_string(), Box::new(black_cubemap()));
+        textures.insert(ibl::PREFILTERED_MAP.to_string(), Box::new(black_cubemap()));
+
+        let shadow_maps = ShadowMaps::new(&_gl);
+        textures.insert(
+            shadows::SHADOW_MAPS.to_string(),
+            Box::new(shadow_maps.texture().clone()),
+        );
+
+        let lit = lighting::lit_shader().compile(&_gl);
+        bind_engine_blocks(&lit);
+        let pbr = ibl::pbr_shader().compile(&_gl);
+        bind_engine_blocks(&pbr);
+        let mut shaders = HashMap::new();
+        shaders.insert(DEFAULT_SHADER.to_string(), shader);
+        shaders.insert(LIT_SHADER.to_string(), lit);
+        shaders.insert(PBR_SHADER.to_string(), pbr);
+
+        let camera_position = Vector3::new(0., 0., 3.);
+        let camera_target = Vector3::zero();
+        let view = lookat3(camera_position, camera_target, Vector3::new(0., 1., 0.));
+
         Self {
-            shader: shader.compile(&_gl),
+            shaders,
             _gl,
             window,
             reciever,
@@ -77,9 +180,28 @@ impl UnsafeEngine {
             objects: vec![],
             commands: RefCell::new(vec![]),
             meshes: HashMap::new(),
-            textures: HashMap::new(),
+            textures,
+            missing_texture,
+            materials: HashMap::new(),
+            framebuffers: HashMap::new(),
+            render_target: None,
+            post_process: None,
+            skybox: None,
+            ibl_baker: None,
+            shadow_maps,
+            shadow_quality: ShadowQuality::default(),
             time_diff: Duration::from_secs(0),
-            projection: perspective3(10000., 0.01, 800. / 600., 45.),
+            projection: perspective3(CAMERA_FAR, CAMERA_NEAR, 800. / 600., CAMERA_FOV),
+            view,
+            camera_position,
+            camera_target,
+            camera_block: UniformBuffer::new(CAMERA_BLOCK_BINDING),
+            lights_block: UniformBuffer::new(LIGHTS_BLOCK_BINDING),
+            shadows_block: UniformBuffer::new(SHADOWS_BLOCK_BINDING),
+            ambient_light: Vec3::new(0.03, 0.03, 0.03),
+            material_block: BufferObject::uniform_buffer_object(),
+            srgb_framebuffer: true,
+            render_stats: RenderStats::default(),
             egui,
         }
     }
@@ -89,15 +211,269 @@ impl UnsafeEngine {
     }
 
     pub fn add_mesh<Name: Into<String>>(&mut self, name: Name, mesh: Mesh) {
-        self.meshes
-            .insert(name.into(), mesh.create_static(&self.shader));
+        let mesh = mesh.create_static(self.access_shader());
+        self.meshes.insert(name.into(), mesh);
     }
 
     pub fn add_texture<Name: Into<String>>(&mut self, name: Name, texture: BuilderTexture2D) {
+        let texture = texture.process(self.access_shader());
+        self.textures.insert(name.into(), Box::new(texture));
+    }
+
+    pub fn add_compressed_texture<Name: Into<String>>(
+        &mut self,
+        name: Name,
+        texture: BuilderCompressedTexture2D,
+    ) -> Result<(), textures::Error> {
+        let texture = texture.process()?;
+        self.textures.insert(name.into(), Box::new(texture));
+        Ok(())
+    }
+
+    pub fn add_cubemap<Name: Into<String>>(&mut self, name: Name, cubemap: BuilderTextureCube) {
+        self.textures
+            .insert(name.into(), Box::new(cubemap.process()));
+    }
+
+    pub fn add_texture_array<Name: Into<String>>(
+        &mut self,
+        name: Name,
+        texture: BuilderTexture2DArray,
+    ) {
+        self.textures
+            .insert(name.into(), Box::new(texture.process()));
+    }
+
+    pub fn add_texture_3d<Name: Into<String>>(&mut self, name: Name, texture: BuilderTexture3D) {
+        self.textures
+            .insert(name.into(), Box::new(texture.process()));
+    }
+
+    pub fn get_texture(&self, name: &str) -> Option<&dyn Texture> {
+        self.textures.get(name).map(|t| t.as_ref())
+    }
+
+    /// For resizing textures, updating and reading back only needs [`Self::get_texture`].
+    pub fn get_texture_2d_mut(&mut self, name: &str) -> Option<&mut Texture2D> {
         self.textures
-            .insert(name.into(), texture.process(&self.shader));
+            .get_mut(name)
+            .and_then(|t| t.as_texture_2d_mut())
+    }
+
+    /// Draws the registered cube map `cubemap` behind the scene, `None` goes back to the plain clear color.
+    pub fn set_skybox<Name: Into<String>>(&mut self, cubemap: Option<Name>) {
+        match (cubemap, &mut self.skybox) {
+            (Some(cubemap), Some(skybox)) => skybox.set_cubemap(cubemap.into()),
+            (Some(cubemap), None) => self.skybox = Some(Skybox::new(&self._gl, cubemap.into())),
+            (None, _) => self.skybox = None,
+        }
+    }
+
+    /// Bakes the registered cube map `cubemap` into the irradiance and prefiltered maps
+    /// PBR materials are lit with. Slow, meant to run at load time rather than per frame.
+    /// `None` removes the image-based lighting. Fails, keeping the current lighting,
+    /// if `cubemap` isn't a registered cube map or the lookup table can't be rendered.
+    pub fn set_environment<Name: Into<String>>(
+        &mut self,
+        cubemap: Option<Name>,
+    ) -> Result<(), ibl::Error> {
+        let Some(cubemap) = cubemap.map(Into::into) else {
+            self.replace_texture(ibl::IRRADIANCE_MAP, Box::new(black_cubemap()));
+            self.replace_texture(ibl::PREFILTERED_MAP, Box::new(black_cubemap()));
+            return Ok(());
+        };
+        let Some(texture) = self.textures.get(&cubemap) else {
+            return Err(ibl::Error::UnknownTexture(cubemap));
+        };
+        if texture.target() != gl::TEXTURE_CUBE_MAP {
+            return Err(ibl::Error::NotACubeMap(texture.target()));
+        }
+
+        if self.ibl_baker.is_none() {
+            let baker = IblBaker::new(&self._gl);
+            let brdf_lut = baker.brdf_lut()?;
+            self.replace_texture(ibl::BRDF_LUT, Box::new(brdf_lut));
+            self.ibl_baker = Some(baker);
+        }
+
+        let baker = self.ibl_baker.as_ref().unwrap();
+        let environment = baker.bake(self.textures[&cubemap].as_ref());
+
+        let (width, height) = self.window.get_framebuffer_size();
+        unsafe { gl::Viewport(0, 0, width, height) };
+
+        let environment = environment?;
+        self.replace_texture(ibl::IRRADIANCE_MAP, Box::new(environment.irradiance));
+        self.replace_texture(ibl::PREFILTERED_MAP, Box::new(environment.prefiltered));
+        Ok(())
+    }
+
+    /// Registers `texture` under `name`, deleting the texture it replaces.
+    /// Only for textures the engine created itself, others may still be in use elsewhere.
+    fn replace_texture(&mut self, name: &str, texture: Box<dyn Texture>) {
+        if let Some(previous) = self.textures.insert(name.to_string(), texture) {
+            unsafe { gl::DeleteTextures(1, &previous.id()) };
+        }
+    }
+
+    pub fn add_shader<Name: Into<String>>(&mut self, name: Name, shader: ShaderSource) {
+        let shader = shader.compile(&self._gl);
+        bind_engine_blocks(&shader);
+        self.shaders.insert(name.into(), shader);
+    }
+
+    /// Accepts typed materials such as [`PbrMaterial`](crate::wrappers::material::PbrMaterial).
+    /// Objects using a material whose shader isn't registered are drawn with the missing texture.
+    pub fn add_material<Name, M>(&mut self, name: Name, material: M)
+    where
+        Name: Into<String>,
+        M: Into<Material>,
+    {
+        let (name, material) = (name.into(), material.into());
+        if !self.shaders.contains_key(material.shader()) {
+            eprintln!(
+                "Material `{name}` uses the unknown shader `{}`, it will be drawn as missing",
+                material.shader()
+            );
+        }
+        self.materials.insert(name, material);
+    }
+
+    /// Color texture attachments are registered as textures under their attachment names,
+    /// so they can be bound to materials like any other texture.
+    pub fn add_framebuffer<Name: Into<String>>(
+        &mut self,
+        name: Name,
+        framebuffer: BuilderFramebuffer,
+    ) -> Result<(), framebuffer::Error> {
+        let (width, height) = self.window.get_framebuffer_size();
+        let framebuffer = match framebuffer.build()? {
+            mut fb if fb.resizes_with_window() => {
+                fb.resize(width as _, height as _)?;
+                fb
+            }
+            fb => fb,
+        };
+
+        self.register_framebuffer_textures(&framebuffer);
+        self.framebuffers.insert(name.into(), framebuffer);
+        Ok(())
+    }
+
+    pub fn get_framebuffer(&self, name: &str) -> Option<&Framebuffer> {
+        self.framebuffers.get(name)
+    }
+
+    /// Draws the scene into the registered framebuffer `framebuffer` instead of the window,
+    /// its first color attachment is then copied to the window.
+    /// Don't sample that framebuffer's textures from the scene while it's the target.
+    pub fn set_render_target<Name: Into<String>>(&mut self, framebuffer: Option<Name>) {
+        self.render_target = framebuffer.map(Into::into);
+    }
+
+    /// Appends `effect` to the post-processing chain, replacing the pass already called `name`.
+    /// Passes can be toggled and reordered later with [`Command::SetPostEffectEnabled`]
+    /// and [`Command::MovePostEffect`].
+    /// Fails if the chain's framebuffers can't be created for the first pass.
+    pub fn add_post_effect<Name: Into<String>>(
+        &mut self,
+        name: Name,
+        effect: PostEffect,
+    ) -> Result<(), framebuffer::Error> {
+        let post_process = match &mut self.post_process {
+            Some(post_process) => post_process,
+            None => {
+                let (width, height) = self.window.get_framebuffer_size();
+                let post_process = PostProcess::new(&self._gl, (width as _, height as _))?;
+                self.post_process.insert(post_process)
+            }
+        };
+        post_process.add(name, effect);
+        Ok(())
+    }
+
+    pub fn post_process(&self) -> Option<&PostProcess> {
+        self.post_process.as_ref()
+    }
+
+    pub fn post_process_mut(&mut self) -> Option<&mut PostProcess> {
+        self.post_process.as_mut()
+    }
+
+    fn active_post_process(&self) -> Option<&PostProcess> {
+        self.post_process.as_ref().filter(|post| post.is_active())
+    }
+
+    fn register_framebuffer_textures(&mut self, framebuffer: &Framebuffer) {
+        framebuffer.textures().for_each(|(name, texture)| {
+            self.textures
+                .insert(name.to_string(), Box::new(texture.clone()));
+        });
+    }
+
+    fn resize_framebuffers(&mut self, width: i32, height: i32) {
+        // Minimized windows report a zero size, which no framebuffer can be complete with.
+        if width <= 0 || height <= 0 {
+            return;
+        }
+
+        let mut framebuffers = std::mem::take(&mut self.framebuffers);
+        framebuffers
+            .iter_mut()
+            .filter(|(_, fb)| fb.resizes_with_window())
+            .for_each(|(name, fb)| match fb.resize(width as _, height as _) {
+                Ok(()) => self.register_framebuffer_textures(fb),
+                Err(e) => eprintln!("Couldn't resize framebuffer `{name}`: {e}"),
+            });
+        self.framebuffers = framebuffers;
+
+        if let Some(post_process) = &mut self.post_process {
+            if let Err(e) = post_process.resize(width as _, height as _) {
+                eprintln!("Couldn't resize the post-processing targets: {e}");
+            }
+        }
+    }
+
+    /// Where the scene is drawn: the user's render target, or the
+    /// post-processing input while any effect is enabled.
+    fn render_target(&self) -> Option<&Framebuffer> {
+        self.render_target
+            .as_ref()
+            .and_then(|name| self.framebuffers.get(name))
+            .or_else(|| self.active_post_process().map(PostProcess::scene_target))
+    }
+
+    /// Gets the scene from the render target to the window, through the post-processing chain if active.
+    fn present(&self, target: &Framebuffer, screen: (i32, i32)) {
+        let input = target.textures().next().map(|(_, texture)| texture);
+        match (self.active_post_process(), input) {
+            (Some(post_process), Some(input)) => {
+                post_process.run(input, &self.shaders, &self.textures, screen)
+            }
+            _ => target.blit_to_screen(0, screen),
+        }
+
+        unsafe { gl::Viewport(0, 0, screen.0, screen.1) };
     }
 
+    /// Encode scene output to sRGB when writing to the default framebuffer.
+    /// Enabled by default, which makes shader output and the background color linear.
+    /// Disable it to keep the old gamma-space behaviour.
+    pub fn set_srgb_framebuffer(&mut self, enabled: bool) {
+        self.srgb_framebuffer = enabled;
+    }
+
+    /// Light reaching every surface regardless of the light sources, linear.
+    pub fn set_ambient_light(&mut self, color: Vec3) {
+        self.ambient_light = color;
+    }
+
+    /// Resolution and filtering of the shadow maps of every light, see [`Light::set_shadows`](crate::object::components::Light::set_shadows).
+    pub fn set_shadow_quality(&mut self, quality: ShadowQuality) {
+        self.shadow_quality = quality;
+    }
+
+    /// `color` is linear while the sRGB framebuffer is enabled.
     pub fn set_background_color(&self, color: Vec4) {
         unsafe {
             gl::ClearColor(color.x, color.y, color.z, color.w);
@@ -111,16 +487,42 @@ impl UnsafeEngine {
     }
 
     fn add_object(&mut self, obj: ObjectConstructor) {
-        let obj = obj.construct(&self.shader);
+        let obj = obj.construct(self.access_shader());
+        self.warn_unknown_references(&obj);
         self.objects.push(obj)
     }
 
+    fn warn_unknown_references(&self, obj: &Object) {
+        let Some(renderer) = obj.renderer() else {
+            return;
+        };
+        if !self.meshes.contains_key(renderer.mesh()) {
+            eprintln!(
+                "Unknown mesh `{}`, the object won't be drawn",
+                renderer.mesh()
+            );
+        }
+        if let Some(material) = renderer
+            .material()
+            .filter(|name| !self.materials.contains_key(*name))
+        {
+            eprintln!("Unknown material `{material}`, the object will be drawn as missing");
+        }
+    }
+
+    /// Draw calls, state changes and culled objects of the last frame.
+    pub fn render_stats(&self) -> RenderStats {
+        self.render_stats
+    }
+
     pub fn get_objects(&self) -> &[Object] {
         &self.objects
     }
 
     fn change_object(&mut self, id: usize, object: ObjectConstructor) {
-        self.objects[id] = object.construct(&self.shader);
+        let object = object.construct(self.access_shader());
+        self.warn_unknown_references(&object);
+        self.objects[id] = object;
     }
 
     pub fn delta_time(&self) -> f32 {
@@ -145,10 +547,16 @@ impl UnsafeEngine {
                     match e {
                         InnerEvent::IngameEvent(e) => return Some(e),
                         InnerEvent::Close => self.window.set_should_close(true),
-                        InnerEvent::Resize(w, h) => unsafe {
-                            self.projection = perspective3(10000.0, 0.01, w as f32 / h as f32, 45.);
-                            gl::Viewport(0, 0, w, h)
-                        },
+                        InnerEvent::Resize(w, h) => {
+                            self.projection = perspective3(
+                                CAMERA_FAR,
+                                CAMERA_NEAR,
+                                w as f32 / h as f32,
+                                CAMERA_FOV,
+                            );
+                            unsafe { gl::Viewport(0, 0, w, h) };
+                            self.resize_framebuffers(w, h);
+                        }
                         _ => (),
                     }
 
@@ -160,17 +568,35 @@ impl UnsafeEngine {
 
             closure(self, events);
 
-            self.clear_background();
+            if self.srgb_framebuffer {
+                unsafe { gl::Enable(gl::FRAMEBUFFER_SRGB) };
+            }
 
+            // Commands may toggle post-processing, which changes the render target.
             let commands = std::mem::take(&mut self.commands);
             commands
                 .into_inner()
                 .into_iter()
                 .for_each(|command| command.interpret(self));
 
-            self.update();
+            let shadow_views = self.update();
 
             let (width, height) = self.window.get_framebuffer_size();
+            let target = self.render_target();
+            if let Some(target) = target {
+                target.bind();
+            }
+            self.clear_background();
+
+            let render_stats = self.render(target, &shadow_views);
+            if let Some(target) = target {
+                self.present(target, (width, height));
+            }
+            self.render_stats = render_stats;
+
+            // egui blends in gamma space and expects a plain framebuffer.
+            unsafe { gl::Disable(gl::FRAMEBUFFER_SRGB) };
+
             self.egui.end_frame((width as _, height as _));
 
             self.window.swap_buffers();
@@ -182,22 +608,207 @@ impl UnsafeEngine {
     }
 
     pub fn access_shader(&self) -> &Shader {
-        &self.shader
+        self.shaders.get(DEFAULT_SHADER).unwrap()
+    }
+
+    pub fn get_shader(&self, name: &str) -> Option<&Shader> {
+        self.shaders.get(name)
+    }
+
+    /// Textures that aren't registered are drawn with the magenta [`Texture2D::missing`] checkerboard.
+    fn texture_or_missing(&self, name: &str) -> &dyn Texture {
+        self.get_texture(name).unwrap_or(&self.missing_texture)
+    }
+
+    /// Uploads the per-frame blocks and makes room for the returned shadow views.
+    fn update(&mut self) -> Vec<ShadowView> {
+        self.camera_block.update(&CameraBlock::new(
+            self.projection,
+            self.view,
+            self.camera_position,
+        ));
+
+        let lights: Vec<_> = self
+            .objects
+            .iter()
+            .filter(|obj| obj.is_enabled())
+            .filter_map(|obj| Some((obj.transform().pos, *obj.light()?)))
+            .collect();
+        let mut lights_block = LightsBlock::new(
+            self.ambient_light,
+            lights.iter().map(|(position, light)| (*position, light)),
+        );
+        let shadow_views = shadows::assign_shadow_maps(
+            &lights,
+            &self.camera_view(),
+            self.shadow_quality.resolution,
+            &mut lights_block,
+        );
+        self.lights_block.update(&lights_block);
+        self.reserve_shadow_maps(shadow_views.len());
+        shadow_views
     }
 
-    fn update(&self) {
+    /// Draws the frame into `target`, or the window, which must already be bound.
+    fn render(&self, target: Option<&Framebuffer>, shadow_views: &[ShadowView]) -> RenderStats {
+        let mut cache = StateCache::new();
+        let (mut opaque, mut transparent) = self.render_queues();
+        // Off-screen objects still cast shadows into view.
+        self.render_shadows(shadow_views, &opaque, target, &mut cache);
+
+        let frustum = Frustum::new(&self.camera_view());
+        let culled = opaque.cull(&frustum) + transparent.cull(&frustum);
+
+        opaque.submit(&mut cache, &self.material_block);
+
+        // The sky fills whatever is left at the far plane, so it must be drawn
+        // before blended objects that don't write depth.
+        BlendMode::Opaque.apply();
+        if let Some(skybox) = &self.skybox {
+            if let Some(cubemap) = self.textures.get(skybox.cubemap()) {
+                skybox.draw(cubemap.as_ref());
+            }
+        }
+        cache.invalidate();
+
+        transparent.submit(&mut cache, &self.material_block);
+        BlendMode::Opaque.apply();
+
+        RenderStats {
+            culled,
+            ..cache.stats()
+        }
+    }
+
+    /// Enabled objects with a renderer. Opaque ones are sorted roughly front to back, then by
+    /// state to save binds and draw calls, blended ones back to front so they composite correctly.
+    fn render_queues(&self) -> (RenderQueue<'_>, RenderQueue<'_>) {
+        let (mut opaque, mut transparent) = (RenderQueue::new(), RenderQueue::new());
         self.objects
             .iter()
             .filter(|obj| obj.is_enabled())
-            .for_each(|obj| {
-                let transform = obj.transform();
-                if let Some(renderer) = obj.renderer() {
-                    let (mesh, texture) = renderer.request();
-                    let mesh = self.meshes.get(mesh).unwrap();
-                    let texture = texture.map(|name| self.textures.get(name)).flatten();
-                    self.shader.draw(transform, mesh, texture, self.projection);
+            .filter_map(|obj| self.draw_item(obj))
+            .for_each(|item| {
+                let blend_mode = item.material.map(Material::blend_mode).unwrap_or_default();
+                match blend_mode.is_transparent() {
+                    true => transparent.push(item),
+                    false => opaque.push(item),
                 }
             });
+
+        opaque.sort_by_state();
+        transparent.sort_back_to_front();
+        (opaque, transparent)
+    }
+
+    /// Resolves the mesh, material and textures `obj`'s renderer refers to.
+    /// Objects whose mesh isn't registered are skipped, they were reported when added.
+    fn draw_item<'a>(&'a self, obj: &'a Object) -> Option<DrawItem<'a>> {
+        let renderer = obj.renderer()?;
+        let mesh = self.meshes.get(renderer.mesh())?;
+        let material = renderer.material().map(|name| {
+            self.materials
+                .get(name)
+                .and_then(|material| Some((material, self.shaders.get(material.shader())?)))
+        });
+
+        let (shader, material, textures) = match material {
+            Some(Some((material, shader))) => {
+                let textures = material
+                    .textures()
+                    .iter()
+                    .chain(renderer.textures())
+                    .map(|(sampler, name)| (sampler.as_str(), self.texture_or_missing(name)));
+                (shader, Some(material), TextureUnit::limit(textures))
+            }
+            // Unknown materials, or ones naming an unknown shader, stand out as missing.
+            Some(None) => {
+                let missing: &dyn Texture = &self.missing_texture;
+                (self.access_shader(), None, vec![("texture1", missing)])
+            }
+            None => {
+                let textures = renderer
+                    .textures()
+                    .iter()
+                    .map(|(sampler, name)| (sampler.as_str(), self.texture_or_missing(name)));
+                (self.access_shader(), None, TextureUnit::limit(textures))
+            }
+        };
+
+        let camera = self.camera_position;
+        let Vector3 { x, y, z } = obj.transform().pos;
+        let (dx, dy, dz) = (x - camera.x, y - camera.y, z - camera.z);
+
+        Some(DrawItem {
+            shader,
+            material,
+            textures,
+            mesh,
+            transform: obj.transform(),
+            distance: dx * dx + dy * dy + dz * dz,
+        })
+    }
+
+    fn camera_view(&self) -> CameraView {
+        let (width, height) = self.window.get_framebuffer_size();
+        CameraView {
+            position: self.camera_position,
+            target: self.camera_target,
+            up: Vector3::new(0., 1., 0.),
+            fov: CAMERA_FOV,
+            aspect: width.max(1) as f32 / height.max(1) as f32,
+            near: CAMERA_NEAR,
+            far: CAMERA_FAR,
+        }
+    }
+
+    fn reserve_shadow_maps(&mut self, layers: usize) {
+        if layers > 0
+            && self
+                .shadow_maps
+                .reserve(self.shadow_quality.resolution, layers)
+        {
+            self.textures.insert(
+                shadows::SHADOW_MAPS.to_string(),
+                Box::new(self.shadow_maps.texture().clone()),
+            );
+        }
+    }
+
+    /// Depth pass of `casters` filling the shadow maps, then back to the scene's `target`.
+    fn render_shadows(
+        &self,
+        views: &[ShadowView],
+        casters: &RenderQueue,
+        target: Option<&Framebuffer>,
+        cache: &mut StateCache,
+    ) {
+        if views.is_empty() {
+            return;
+        }
+
+        BlendMode::Opaque.apply();
+        self.shadows_block
+            .update(&ShadowsBlock::new(views, self.shadow_quality));
+        let mut queue = casters.depth_only(
+            self.shadow_maps.shader(),
+            self.shadow_maps.alpha_test_shader(),
+        );
+        queue.sort_by_state();
+        self.shadow_maps.render(views, || {
+            // Setting the view's uniforms switched programs behind the cache's back.
+            cache.invalidate();
+            queue.submit(cache, &self.material_block);
+        });
+
+        match target {
+            Some(target) => target.bind(),
+            None => unsafe {
+                let (width, height) = self.window.get_framebuffer_size();
+                Framebuffer::unbind();
+                gl::Viewport(0, 0, width, height);
+            },
+        }
     }
 
     fn handle_events(&mut self) -> Vec<InnerEvent> {
@@ -211,6 +822,20 @@ impl UnsafeEngine {
     }
 }
 
+fn black_cubemap() -> TextureCube {
+    TextureCube::from_images([(); 6].map(|_| DynamicImage::new_rgb8(1, 1)))
+        .unwrap()
+        .set_color_space(ColorSpace::Linear)
+        .process()
+}
+
+/// Points the per-frame blocks every engine shader may declare at their bindings.
+fn bind_engine_blocks(shader: &Shader) {
+    shader.bind_uniform_block(CameraBlock::NAME, CAMERA_BLOCK_BINDING);
+    shader.bind_uniform_block(LightsBlock::NAME, LIGHTS_BLOCK_BINDING);
+    shader.bind_uniform_block(ShadowsBlock::NAME, SHADOWS_BLOCK_BINDING);
+}
+
 fn handle_window_event(event: glfw::WindowEvent) -> InnerEvent {
     match event {
         glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => InnerEvent::Close,
@@ -232,6 +857,10 @@ fn handle_window_event(event: glfw::WindowEvent) -> InnerEvent {
 pub enum Command {
     AddObject(ObjectConstructor),
     ChangeObject(usize, ObjectConstructor),
+    SetPostEffectEnabled(String, bool),
+    /// Moves the named pass to the given position in the chain.
+    MovePostEffect(String, usize),
+    SetPostEffect(String, PostEffect),
 }
 
 impl Command {
@@ -239,6 +868,21 @@ impl Command {
         match self {
             Self::AddObject(obj) => engine.add_object(obj),
             Self::ChangeObject(idx, obj) => engine.change_object(idx, obj),
+            Self::SetPostEffectEnabled(name, enabled) => {
+                if let Some(post_process) = &mut engine.post_process {
+                    post_process.set_enabled(&name, enabled);
+                }
+            }
+            Self::MovePostEffect(name, index) => {
+                if let Some(post_process) = &mut engine.post_process {
+                    post_process.move_to(&name, index);
+                }
+            }
+            Self::SetPostEffect(name, effect) => {
+                if let Some(post_process) = &mut engine.post_process {
+                    post_process.set_effect(&name, effect);
+                }
+            }
         }
     }
 }
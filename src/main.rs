@@ -1,10 +1,16 @@
 use engine_math::{Vector2, Vector3, Vector4};
 use unsafe_engine::engine::UnsafeEngine;
-use unsafe_engine::object::{components::Transform, Object, ObjectConstructor};
+use unsafe_engine::object::{
+    components::{Light, Shadows, Transform},
+    Object, ObjectConstructor,
+};
 use unsafe_engine::wrappers::{
+    material::{BlendMode, Material, PbrMaterial},
     mesh::{Mesh, Vertex},
+    post_process::PostEffect,
     shader::ShaderSource,
     textures::Texture2D,
+    types::Uniform,
 };
 use unsafe_engine::Command;
 
@@ -22,7 +28,7 @@ fn main() {
         Vector3::new(-1.3, 1., -1.5),
     ];
 
-    let texture = Texture2D::load("textures/container.jpg", "texture1");
+    let texture = Texture2D::load("textures/container.jpg", "texture1").unwrap();
 
     let shader =
         ShaderSource::from_files("shaders/vertex_shader.glsl", "shaders/fragment_shader.glsl")
@@ -32,7 +38,42 @@ fn main() {
     engine.add_mesh("cube", cube());
     engine.add_texture("texture1", texture);
 
-    engine.set_background_color(Vector4::new(0., 0.1, 0.2, 1.));
+    let solid = ShaderSource::from_files(
+        "shaders/vertex_shader.glsl",
+        "shaders/fragment_shader_2.glsl",
+    )
+    .unwrap();
+    engine.add_shader("solid", solid);
+    engine.add_material("solid", Material::new("solid"));
+    engine.add_material("lit", Material::lit().set_texture("diffuseMap", "texture1"));
+    engine.add_material(
+        "glass",
+        Material::lit()
+            .set_uniform(
+                "diffuseColor",
+                Uniform::Vector4(Vector4::new(0.6, 0.8, 1., 0.4)),
+            )
+            .set_blend_mode(BlendMode::AlphaBlend),
+    );
+    engine.add_material(
+        "pbr",
+        PbrMaterial::new()
+            .set_albedo_map("texture1")
+            .set_metallic(0.8)
+            .set_roughness(0.3),
+    );
+    engine.command(Command::AddObject(Object::new().set_light(
+        Light::directional(Vector3::new(-0.2, -1., -0.3)).set_shadows(Shadows::default()),
+    )));
+
+    engine.set_background_color(Vector4::new(0., 0.01, 0.033, 1.));
+
+    engine
+        .add_post_effect("tonemap", PostEffect::tonemap())
+        .unwrap();
+    engine
+        .add_post_effect("vignette", PostEffect::vignette())
+        .unwrap();
 
     let mut i = 0;
     engine.draw_loop(|engine, _| {
@@ -48,6 +89,23 @@ fn main() {
             ui.with_layout(
                 egui::Layout::bottom_up(egui::Align::Center).with_cross_justify(true),
                 |ui| {
+                    let stats = engine.render_stats();
+                    ui.label(format!(
+                        "{} draw calls, {} state changes, {} culled",
+                        stats.draw_calls,
+                        stats.state_changes(),
+                        stats.culled
+                    ));
+
+                    let passes = engine.post_process().map(|post| post.passes());
+                    passes.unwrap_or_default().iter().for_each(|pass| {
+                        let mut enabled = pass.enabled;
+                        if ui.checkbox(&mut enabled, &pass.name).changed() {
+                            engine
+                                .command(Command::SetPostEffectEnabled(pass.name.clone(), enabled));
+                        }
+                    });
+
                     if ui.button("add obj").clicked() {
                         engine.command(Command::AddObject(
                             Object::new()
@@ -60,6 +118,58 @@ fn main() {
                         ));
                         i += 1;
                     }
+
+                    if ui.button("add lit obj").clicked() {
+                        engine.command(Command::AddObject(
+                            Object::new()
+                                .set_mesh_name("cube")
+                                .set_material_name("lit")
+                                .set_transform(Transform {
+                                    pos: positions[i % positions.len()],
+                                    ..Default::default()
+                                }),
+                        ));
+                        i += 1;
+                    }
+
+                    if ui.button("add pbr obj").clicked() {
+                        engine.command(Command::AddObject(
+                            Object::new()
+                                .set_mesh_name("cube")
+                                .set_material_name("pbr")
+                                .set_transform(Transform {
+                                    pos: positions[i % positions.len()],
+                                    ..Default::default()
+                                }),
+                        ));
+                        i += 1;
+                    }
+
+                    if ui.button("add glass obj").clicked() {
+                        engine.command(Command::AddObject(
+                            Object::new()
+                                .set_mesh_name("cube")
+                                .set_material_name("glass")
+                                .set_transform(Transform {
+                                    pos: positions[i % positions.len()],
+                                    ..Default::default()
+                                }),
+                        ));
+                        i += 1;
+                    }
+
+                    if ui.button("add solid obj").clicked() {
+                        engine.command(Command::AddObject(
+                            Object::new()
+                                .set_mesh_name("cube")
+                                .set_material_name("solid")
+                                .set_transform(Transform {
+                                    pos: positions[i % positions.len()],
+                                    ..Default::default()
+                                }),
+                        ));
+                        i += 1;
+                    }
                 },
             );
         });
@@ -93,154 +203,226 @@ fn cube() -> Mesh {
         Vertex {
             pos: Vector3::new(-0.5, -0.5, -0.5),
             tex: Vector2::new(0., 0.),
+            normal: Vector3::new(0., 0., -1.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(0.5, -0.5, -0.5),
             tex: Vector2::new(1., 0.),
+            normal: Vector3::new(0., 0., -1.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(0.5, 0.5, -0.5),
             tex: Vector2::new(1., 1.),
+            normal: Vector3::new(0., 0., -1.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(0.5, 0.5, -0.5),
             tex: Vector2::new(1., 1.),
+            normal: Vector3::new(0., 0., -1.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(-0.5, 0.5, -0.5),
             tex: Vector2::new(0., 1.),
+            normal: Vector3::new(0., 0., -1.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(-0.5, -0.5, -0.5),
             tex: Vector2::new(0., 0.),
+            normal: Vector3::new(0., 0., -1.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         // ------
         Vertex {
             pos: Vector3::new(-0.5, -0.5, 0.5),
             tex: Vector2::new(0., 0.),
+            normal: Vector3::new(0., 0., 1.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(0.5, -0.5, 0.5),
             tex: Vector2::new(1., 0.),
+            normal: Vector3::new(0., 0., 1.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(0.5, 0.5, 0.5),
             tex: Vector2::new(1., 1.),
+            normal: Vector3::new(0., 0., 1.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(0.5, 0.5, 0.5),
             tex: Vector2::new(1., 1.),
+            normal: Vector3::new(0., 0., 1.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(-0.5, 0.5, 0.5),
             tex: Vector2::new(0., 1.),
+            normal: Vector3::new(0., 0., 1.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(-0.5, -0.5, 0.5),
             tex: Vector2::new(0., 0.),
+            normal: Vector3::new(0., 0., 1.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         // ------
         Vertex {
             pos: Vector3::new(-0.5, 0.5, 0.5),
             tex: Vector2::new(1., 0.),
+            normal: Vector3::new(-1., 0., 0.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(-0.5, 0.5, -0.5),
             tex: Vector2::new(1., 1.),
+            normal: Vector3::new(-1., 0., 0.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(-0.5, -0.5, -0.5),
             tex: Vector2::new(0., 1.),
+            normal: Vector3::new(-1., 0., 0.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(-0.5, -0.5, -0.5),
             tex: Vector2::new(0., 1.),
+            normal: Vector3::new(-1., 0., 0.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(-0.5, -0.5, 0.5),
             tex: Vector2::new(0., 0.),
+            normal: Vector3::new(-1., 0., 0.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(-0.5, 0.5, 0.5),
             tex: Vector2::new(1., 0.),
+            normal: Vector3::new(-1., 0., 0.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         // ------
         Vertex {
             pos: Vector3::new(0.5, 0.5, 0.5),
             tex: Vector2::new(1., 0.),
+            normal: Vector3::new(1., 0., 0.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(0.5, 0.5, -0.5),
             tex: Vector2::new(1., 1.),
+            normal: Vector3::new(1., 0., 0.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(0.5, -0.5, -0.5),
             tex: Vector2::new(0., 1.),
+            normal: Vector3::new(1., 0., 0.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(0.5, -0.5, -0.5),
             tex: Vector2::new(0., 1.),
+            normal: Vector3::new(1., 0., 0.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(0.5, -0.5, 0.5),
             tex: Vector2::new(0., 0.),
+            normal: Vector3::new(1., 0., 0.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(0.5, 0.5, 0.5),
             tex: Vector2::new(1., 0.),
+            normal: Vector3::new(1., 0., 0.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         // ------
         Vertex {
             pos: Vector3::new(-0.5, -0.5, -0.5),
             tex: Vector2::new(0., 1.),
+            normal: Vector3::new(0., -1., 0.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(0.5, -0.5, -0.5),
             tex: Vector2::new(1., 1.),
+            normal: Vector3::new(0., -1., 0.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(0.5, -0.5, 0.5),
             tex: Vector2::new(1., 0.),
+            normal: Vector3::new(0., -1., 0.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(0.5, -0.5, 0.5),
             tex: Vector2::new(1., 0.),
+            normal: Vector3::new(0., -1., 0.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(-0.5, -0.5, 0.5),
             tex: Vector2::new(0., 0.),
+            normal: Vector3::new(0., -1., 0.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(-0.5, -0.5, -0.5),
             tex: Vector2::new(0., 1.),
+            normal: Vector3::new(0., -1., 0.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         // ------
         Vertex {
             pos: Vector3::new(-0.5, 0.5, -0.5),
             tex: Vector2::new(0., 1.),
+            normal: Vector3::new(0., 1., 0.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(0.5, 0.5, -0.5),
             tex: Vector2::new(1., 1.),
+            normal: Vector3::new(0., 1., 0.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(0.5, 0.5, 0.5),
             tex: Vector2::new(1., 0.),
+            normal: Vector3::new(0., 1., 0.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(0.5, 0.5, 0.5),
             tex: Vector2::new(1., 0.),
+            normal: Vector3::new(0., 1., 0.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(-0.5, 0.5, 0.5),
             tex: Vector2::new(0., 0.),
+            normal: Vector3::new(0., 1., 0.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
         Vertex {
             pos: Vector3::new(-0.5, 0.5, -0.5),
             tex: Vector2::new(0., 1.),
+            normal: Vector3::new(0., 1., 0.),
+            tangent: Vector4::new(0., 0., 0., 0.),
         },
     ];
 
     let indices = (0..36).collect();
-    Mesh::new(vertices, indices)
+    Mesh::new(vertices, indices).with_computed_tangents()
 }
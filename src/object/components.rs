@@ -1,14 +1,18 @@
-use engine_math::Vector3;
+use engine_math::{
+    transform::homogeneous::{rotate3, scale3, translate3},
+    Matrix4, Vector3,
+};
 
 use crate::wrappers::{
     mesh::{BoundStaticMesh, Draw},
-    textures::Texture2D,
+    textures::{Texture, Texture2D},
     types::TextureUnit,
 };
 
 #[derive(Debug, Clone)]
 pub struct Components {
     pub renderer: Option<Renderer>,
+    pub light: Option<Light>,
 }
 
 #[derive(Debug, Clone)]
@@ -19,6 +23,14 @@ pub struct Transform {
     pub enabled: bool,
 }
 
+impl Transform {
+    /// Scales, then rotates around x, y and z, then translates.
+    pub fn model_matrix(&self) -> Matrix4 {
+        let Vector3 { x, y, z } = self.rotation;
+        translate3(self.pos) * scale3(self.scale) * rotate3(x, y, z)
+    }
+}
+
 impl Default for Transform {
     fn default() -> Self {
         Self {
@@ -33,21 +45,201 @@ impl Default for Transform {
 #[derive(Debug, Clone)]
 pub struct Renderer {
     mesh: String,
-    textures: Vec<String>,
+    textures: Vec<(String, String)>,
+    material: Option<String>,
 }
 
 impl Renderer {
-    pub fn new(mesh: String, textures: Vec<String>) -> Self {
-        Self { mesh, textures }
+    /// `textures` pairs sampler uniform names with texture names.
+    pub fn new(mesh: String, textures: Vec<(String, String)>, material: Option<String>) -> Self {
+        Self {
+            mesh,
+            textures,
+            material,
+        }
+    }
+
+    pub fn mesh(&self) -> &str {
+        &self.mesh
     }
 
-    pub fn request(&self) -> (&str, Option<&str>) {
-        (&self.mesh, self.textures.get(0).map(|s| s.as_str()))
+    pub fn textures(&self) -> &[(String, String)] {
+        &self.textures
+    }
+
+    pub fn material(&self) -> Option<&str> {
+        self.material.as_deref()
     }
 
     pub fn draw(&self, mesh: &BoundStaticMesh, texture: Option<&Texture2D>) {
-        texture.map(|t| t.bind(TextureUnit::Texture0));
+        if let Some(texture) = texture {
+            texture.bind(TextureUnit(0));
+        }
 
         mesh.draw();
     }
 }
+
+/// Distance falloff of point and spot lights, `1 / (constant + linear * d + quadratic * d^2)`.
+#[derive(Debug, Clone, Copy)]
+pub struct Attenuation {
+    pub constant: f32,
+    pub linear: f32,
+    pub quadratic: f32,
+}
+
+/// Reciprocal of the falloff [`Attenuation::range`] fades out to at its range, about 1%.
+const RANGE_FALLOFF: f32 = 1. + 4.5 + 75.;
+
+impl Attenuation {
+    /// Falloff that fades out to about 1% at `range`.
+    pub fn range(range: f32) -> Self {
+        Self {
+            constant: 1.,
+            linear: 4.5 / range,
+            quadratic: 75. / (range * range),
+        }
+    }
+
+    /// Distance at which the falloff drops to the level [`Self::range`] fades out to,
+    /// so it's the inverse of [`Self::range`].
+    pub fn cutoff_distance(&self) -> f32 {
+        let (c, l, q) = (self.constant, self.linear, self.quadratic);
+        if q > 0. {
+            (-l + (l * l - 4. * q * (c - RANGE_FALLOFF)).sqrt()) / (2. * q)
+        } else if l > 0. {
+            (RANGE_FALLOFF - c) / l
+        } else {
+            f32::INFINITY
+        }
+    }
+}
+
+/// Shadow casting settings of a light. Point lights don't cast shadows.
+#[derive(Debug, Clone, Copy)]
+pub struct Shadows {
+    /// Depth offset against shadow acne, in shadow map depth units.
+    pub bias: f32,
+    /// Offset of the sampled position along the surface normal, in world units.
+    pub normal_bias: f32,
+    /// Cascades splitting the view for directional lights, each taking one shadow map.
+    pub cascades: u32,
+    /// How far from the camera directional lights cast shadows.
+    pub distance: f32,
+}
+
+impl Default for Shadows {
+    fn default() -> Self {
+        Self {
+            bias: 0.002,
+            normal_bias: 0.02,
+            cascades: 4,
+            distance: 50.,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum LightKind {
+    Directional {
+        direction: Vector3,
+    },
+    Point {
+        attenuation: Attenuation,
+    },
+    /// Full intensity inside `inner_angle`, fading out to nothing at `outer_angle`.
+    /// Angles are in degrees, measured from `direction`.
+    Spot {
+        direction: Vector3,
+        attenuation: Attenuation,
+        inner_angle: f32,
+        outer_angle: f32,
+    },
+}
+
+/// Light source placed at its object's position. Directions don't need to be normalized.
+#[derive(Debug, Clone, Copy)]
+pub struct Light {
+    pub kind: LightKind,
+    /// Linear color.
+    pub color: Vector3,
+    pub intensity: f32,
+    pub shadows: Option<Shadows>,
+}
+
+impl Light {
+    pub fn new(kind: LightKind) -> Self {
+        Self {
+            kind,
+            color: Vector3::new(1., 1., 1.),
+            intensity: 1.,
+            shadows: None,
+        }
+    }
+
+    pub fn directional(direction: Vector3) -> Self {
+        Self::new(LightKind::Directional { direction })
+    }
+
+    pub fn point(range: f32) -> Self {
+        Self::new(LightKind::Point {
+            attenuation: Attenuation::range(range),
+        })
+    }
+
+    pub fn spot(direction: Vector3, range: f32, inner_angle: f32, outer_angle: f32) -> Self {
+        Self::new(LightKind::Spot {
+            direction,
+            attenuation: Attenuation::range(range),
+            inner_angle,
+            outer_angle,
+        })
+    }
+
+    pub fn set_color(mut self, color: Vector3) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn set_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn set_shadows(mut self, shadows: Shadows) -> Self {
+        self.shadows = Some(shadows);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cutoff_distance_inverts_range() {
+        for range in [0.5, 1., 7., 32., 600.] {
+            let cutoff = Attenuation::range(range).cutoff_distance();
+            assert!(
+                (cutoff - range).abs() <= range * 1e-4,
+                "{range} gave {cutoff}"
+            );
+        }
+    }
+
+    #[test]
+    fn cutoff_distance_without_quadratic_term() {
+        let linear = Attenuation {
+            constant: 1.,
+            linear: 0.5,
+            quadratic: 0.,
+        };
+        assert_eq!(linear.cutoff_distance(), 159.);
+
+        let constant = Attenuation {
+            linear: 0.,
+            ..linear
+        };
+        assert_eq!(constant.cutoff_distance(), f32::INFINITY);
+    }
+}
@@ -1,6 +1,6 @@
 use crate::wrappers::shader::Shader;
 
-use self::components::{Components, Renderer, Transform};
+use self::components::{Components, Light, Renderer, Transform};
 
 pub mod components;
 
@@ -29,6 +29,10 @@ impl Object {
         self.components.renderer.as_ref()
     }
 
+    pub fn light(&self) -> Option<&Light> {
+        self.components.light.as_ref()
+    }
+
     pub fn is_enabled(&self) -> bool {
         self.transform.enabled
     }
@@ -38,7 +42,9 @@ impl Object {
 pub struct ObjectConstructor {
     transform: Transform,
     mesh_name: Option<String>,
-    texture_name: Option<String>,
+    textures: Vec<(String, String)>,
+    material_name: Option<String>,
+    light: Option<Light>,
 }
 
 impl ObjectConstructor {
@@ -47,8 +53,34 @@ impl ObjectConstructor {
         self
     }
 
-    pub fn set_texture_name<N: Into<String>>(mut self, texture_name: N) -> Self {
-        self.texture_name = Some(texture_name.into());
+    /// Binds `texture_name` to the sampler uniform of the same name.
+    pub fn set_texture_name<N: Into<String>>(self, texture_name: N) -> Self {
+        let texture_name = texture_name.into();
+        self.add_texture(texture_name.clone(), texture_name)
+    }
+
+    /// Binds `texture` to the sampler uniform `sampler`, texture units are assigned by the engine.
+    pub fn add_texture<S, T>(mut self, sampler: S, texture: T) -> Self
+    where
+        S: Into<String>,
+        T: Into<String>,
+    {
+        let sampler = sampler.into();
+        let texture = texture.into();
+        match self.textures.iter_mut().find(|(s, _)| *s == sampler) {
+            Some(slot) => slot.1 = texture,
+            None => self.textures.push((sampler, texture)),
+        }
+        self
+    }
+
+    pub fn set_material_name<N: Into<String>>(mut self, material_name: N) -> Self {
+        self.material_name = Some(material_name.into());
+        self
+    }
+
+    pub fn set_light(mut self, light: Light) -> Self {
+        self.light = Some(light);
         self
     }
 
@@ -61,38 +93,38 @@ impl ObjectConstructor {
         let transform = self.transform;
 
         let renderer = if let Some(mesh_name) = self.mesh_name {
-            let mut textures = vec![];
-            if let Some(t) = self.texture_name {
-                textures.push(t);
-            }
-            Some(Renderer::new(mesh_name, textures))
+            Some(Renderer::new(mesh_name, self.textures, self.material_name))
         } else {
             None
         };
 
         Object {
             transform,
-            components: Components { renderer },
+            components: Components {
+                renderer,
+                light: self.light,
+            },
         }
     }
 }
 
 impl From<Object> for ObjectConstructor {
     fn from(value: Object) -> Self {
-        let req = value.renderer().map(|r| r.request());
-        if let Some((mesh_name, texture_name)) = req {
-            let mesh_name = Some(mesh_name.to_string());
-            let texture_name = texture_name.map(|s| s.to_string());
+        if let Some(renderer) = value.renderer() {
             ObjectConstructor {
                 transform: value.transform().clone(),
-                mesh_name,
-                texture_name,
+                mesh_name: Some(renderer.mesh().to_string()),
+                textures: renderer.textures().to_vec(),
+                material_name: renderer.material().map(|s| s.to_string()),
+                light: value.light().copied(),
             }
         } else {
             ObjectConstructor {
                 transform: value.transform().clone(),
                 mesh_name: None,
-                texture_name: None,
+                textures: vec![],
+                material_name: None,
+                light: value.light().copied(),
             }
         }
     }
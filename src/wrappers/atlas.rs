@@ -0,0 +1,296 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use image::{DynamicImage, GenericImageView, RgbaImage};
+
+use super::{
+    textures::BuilderTexture2D,
+    types::{Vec2, Vec4},
+};
+
+/// Placement of one image inside an atlas, in pixels and in texture coordinates.
+/// `v = 0` is the first row of the packed image, as with any other uploaded image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AtlasRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub uv_min: Vec2,
+    pub uv_max: Vec2,
+}
+
+impl AtlasRegion {
+    /// Maps a `[0, 1]` coordinate of the original image into the atlas.
+    pub fn map_uv(&self, uv: Vec2) -> Vec2 {
+        Vec2::new(
+            self.uv_min.x + (self.uv_max.x - self.uv_min.x) * uv.x,
+            self.uv_min.y + (self.uv_max.y - self.uv_min.y) * uv.y,
+        )
+    }
+
+    /// `(offset.x, offset.y, scale.x, scale.y)`, for remapping coordinates in a shader
+    /// as `uv * rect.zw + rect.xy`.
+    pub fn uv_rect(&self) -> Vec4 {
+        Vec4::new(
+            self.uv_min.x,
+            self.uv_min.y,
+            self.uv_max.x - self.uv_min.x,
+            self.uv_max.y - self.uv_min.y,
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TextureAtlas {
+    pub texture: BuilderTexture2D,
+    pub regions: HashMap<String, AtlasRegion>,
+}
+
+impl TextureAtlas {
+    pub fn builder() -> BuilderTextureAtlas {
+        BuilderTextureAtlas {
+            images: vec![],
+            padding: 2,
+            max_size: 8192,
+        }
+    }
+
+    pub fn region(&self, name: &str) -> Option<&AtlasRegion> {
+        self.regions.get(name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BuilderTextureAtlas {
+    images: Vec<(String, DynamicImage)>,
+    padding: u32,
+    max_size: u32,
+}
+
+impl BuilderTextureAtlas {
+    pub fn add_image<N: Into<String>>(mut self, name: N, image: DynamicImage) -> Self {
+        self.images.push((name.into(), image));
+        self
+    }
+
+    /// Packs the texture's image under the name it was loaded with.
+    pub fn add_texture(self, texture: BuilderTexture2D) -> Self {
+        let name = texture.name().to_string();
+        self.add_image(name, texture.into_image())
+    }
+
+    /// Empty pixels kept around every image, filled by repeating its edge so
+    /// filtering and mipmapping don't bleed neighbours in. Defaults to 2.
+    pub fn set_padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn set_max_size(mut self, max_size: u32) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Packs all images with a shelf packer, trying power-of-two sizes from the smallest
+    /// that could fit. Fails if two images share a name or they don't fit in `max_size` squared.
+    /// `name` is the sampler uniform the resulting texture is bound to.
+    pub fn pack(self, name: &str) -> Result<TextureAtlas, Error> {
+        let Self {
+            mut images,
+            padding,
+            max_size,
+        } = self;
+
+        let mut names = HashSet::new();
+        if let Some((duplicate, _)) = images.iter().find(|(name, _)| !names.insert(name.as_str())) {
+            return Err(Error::DuplicateName(duplicate.clone()));
+        }
+
+        images.sort_by_key(|(_, img)| std::cmp::Reverse(img.height()));
+
+        let cell = |img: &DynamicImage| (img.width() + 2 * padding, img.height() + 2 * padding);
+        let area: u32 = images
+            .iter()
+            .map(|(_, img)| {
+                let (w, h) = cell(img);
+                w * h
+            })
+            .sum();
+        let widest = images.iter().map(|(_, img)| cell(img).0).max().unwrap_or(1);
+
+        let mut width = ((area as f32).sqrt() as u32)
+            .max(widest)
+            .next_power_of_two();
+        let mut height = width;
+        let placements = loop {
+            if width > max_size || height > max_size {
+                return Err(Error::TooLarge { max_size });
+            }
+            if let Some(placements) = shelf_pack(&images, padding, width, height) {
+                break placements;
+            }
+            if height < width {
+                height *= 2;
+            } else {
+                width *= 2;
+            }
+        };
+
+        let mut atlas = RgbaImage::new(width, height);
+        let mut regions = HashMap::new();
+        for ((name, img), (x, y)) in images.iter().zip(placements) {
+            let img = img.to_rgba8();
+            blit_with_bleed(&mut atlas, &img, x, y, padding);
+
+            let (w, h) = img.dimensions();
+            regions.insert(
+                name.clone(),
+                AtlasRegion {
+                    x,
+                    y,
+                    width: w,
+                    height: h,
+                    uv_min: Vec2::new(x as f32 / width as f32, y as f32 / height as f32),
+                    uv_max: Vec2::new(
+                        (x + w) as f32 / width as f32,
+                        (y + h) as f32 / height as f32,
+                    ),
+                },
+            );
+        }
+
+        Ok(TextureAtlas {
+            texture: BuilderTexture2D::new(DynamicImage::ImageRgba8(atlas), name.into()),
+            regions,
+        })
+    }
+}
+
+/// Top-left corners of the image contents, padding excluded.
+fn shelf_pack(
+    images: &[(String, DynamicImage)],
+    padding: u32,
+    width: u32,
+    height: u32,
+) -> Option<Vec<(u32, u32)>> {
+    let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+
+    images
+        .iter()
+        .map(|(_, img)| {
+            let (w, h) = (img.width() + 2 * padding, img.height() + 2 * padding);
+            if x + w > width {
+                x = 0;
+                y += shelf_height;
+                shelf_height = 0;
+            }
+            if x + w > width || y + h > height {
+                return None;
+            }
+
+            let placement = (x + padding, y + padding);
+            x += w;
+            shelf_height = shelf_height.max(h);
+            Some(placement)
+        })
+        .collect()
+}
+
+fn blit_with_bleed(atlas: &mut RgbaImage, img: &RgbaImage, x: u32, y: u32, padding: u32) {
+    let (w, h) = img.dimensions();
+    if w == 0 || h == 0 {
+        return;
+    }
+
+    let padding = padding as i64;
+    for dy in -padding..h as i64 + padding {
+        for dx in -padding..w as i64 + padding {
+            let sx = dx.clamp(0, w as i64 - 1) as u32;
+            let sy = dy.clamp(0, h as i64 - 1) as u32;
+            let (ax, ay) = ((x as i64 + dx) as u32, (y as i64 + dy) as u32);
+            if atlas.in_bounds(ax, ay) {
+                atlas.put_pixel(ax, ay, *img.get_pixel(sx, sy));
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    DuplicateName(String),
+    TooLarge { max_size: u32 },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::DuplicateName(name) => write!(f, "Atlas already has an image named {name}"),
+            Error::TooLarge { max_size } => {
+                write!(f, "Images don't fit in a {max_size}x{max_size} atlas")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn images(sizes: &[(u32, u32)]) -> Vec<(String, DynamicImage)> {
+        sizes
+            .iter()
+            .enumerate()
+            .map(|(i, &(w, h))| (i.to_string(), DynamicImage::new_rgba8(w, h)))
+            .collect()
+    }
+
+    #[test]
+    fn shelf_pack_fills_a_row() {
+        let placements = shelf_pack(&images(&[(8, 8), (8, 4), (16, 2)]), 0, 32, 8);
+        assert_eq!(placements, Some(vec![(0, 0), (8, 0), (16, 0)]));
+    }
+
+    #[test]
+    fn shelf_pack_starts_a_new_shelf_below_the_tallest() {
+        let placements = shelf_pack(&images(&[(8, 8), (8, 4), (8, 4)]), 0, 16, 16);
+        assert_eq!(placements, Some(vec![(0, 0), (8, 0), (0, 8)]));
+    }
+
+    #[test]
+    fn shelf_pack_rejects_what_does_not_fit() {
+        assert_eq!(shelf_pack(&images(&[(17, 1)]), 0, 16, 16), None);
+        assert_eq!(shelf_pack(&images(&[(16, 16), (1, 1)]), 0, 16, 16), None);
+    }
+
+    #[test]
+    fn shelf_pack_pads_every_side() {
+        let placements = shelf_pack(&images(&[(4, 4), (4, 4)]), 2, 16, 16);
+        assert_eq!(placements, Some(vec![(2, 2), (10, 2)]));
+
+        // 8 pixels of content plus the padding needs 12.
+        assert_eq!(shelf_pack(&images(&[(8, 8)]), 2, 11, 16), None);
+    }
+
+    #[test]
+    fn pack_rejects_duplicate_names() {
+        let result = TextureAtlas::builder()
+            .add_image("a", DynamicImage::new_rgba8(4, 4))
+            .add_image("a", DynamicImage::new_rgba8(2, 2))
+            .pack("atlas");
+        assert!(matches!(result, Err(Error::DuplicateName(name)) if name == "a"));
+    }
+
+    #[test]
+    fn pack_respects_max_size() {
+        let result = TextureAtlas::builder()
+            .add_image("a", DynamicImage::new_rgba8(16, 16))
+            .set_max_size(16)
+            .pack("atlas");
+        assert!(matches!(result, Err(Error::TooLarge { max_size: 16 })));
+    }
+}
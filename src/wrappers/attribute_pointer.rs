@@ -2,6 +2,8 @@ use std::{ffi::c_void, ptr};
 
 use super::types::DataType;
 
+/// Layout of a vertex or instance struct. Per-instance attributes set a non-zero `divisor`
+/// and are drawn with [`BoundStaticMesh::draw_instanced`](super::mesh::BoundStaticMesh::draw_instanced).
 pub trait Attributes {
     fn get_attributes() -> Vec<AttributePointers>;
 }
@@ -14,6 +16,8 @@ pub struct AttributePointers {
     pub normalized: bool,
     pub stride: usize,
     pub ptr: *const c_void,
+    /// Instances drawn before the attribute advances, `0` advances it per vertex.
+    pub divisor: u32,
 }
 
 impl AttributePointers {
@@ -25,6 +29,7 @@ impl AttributePointers {
             normalized: false,
             stride: 0,
             ptr: ptr::null(),
+            divisor: 0,
         }
     }
 }
@@ -31,6 +31,14 @@ impl BufferObject {
         Self::new(BufferObjectType::ElementArrayBuffer, DrawType::StaticDraw)
     }
 
+    pub fn uniform_buffer_object() -> Self {
+        Self::new(BufferObjectType::UniformBuffer, DrawType::DynamicDraw)
+    }
+
+    pub fn shader_storage_buffer_object() -> Self {
+        Self::new(BufferObjectType::ShaderStorageBuffer, DrawType::DynamicCopy)
+    }
+
     pub fn bind(&self) {
         unsafe {
             gl::BindBuffer(self.bo_type.into(), self.id);
@@ -48,4 +56,28 @@ impl BufferObject {
             );
         }
     }
+
+    /// Reads `len` elements back from the start of the buffer, binding it first.
+    pub fn read_data<T: Copy + Default>(&self, len: usize) -> Vec<T> {
+        let mut data = vec![T::default(); len];
+        self.bind();
+        unsafe {
+            gl::GetBufferSubData(
+                self.bo_type.into(),
+                0,
+                (std::mem::size_of::<T>() * len) as _,
+                data.as_mut_ptr().cast(),
+            );
+        }
+        data
+    }
+
+    /// Binds the buffer to an indexed binding point,
+    /// only meaningful for indexed targets such as `UniformBuffer`
+    /// and `ShaderStorageBuffer`.
+    pub fn bind_base(&self, binding: u32) {
+        unsafe {
+            gl::BindBufferBase(self.bo_type.into(), binding, self.id);
+        }
+    }
 }
@@ -0,0 +1,615 @@
+use std::{fs, path::Path};
+
+use ddsfile::{D3DFormat, Dds, DxgiFormat};
+use gl::types::{GLenum, GLuint};
+use ktx2::Format;
+
+use super::{
+    gl::has_extension,
+    textures::{Error, Texture2D, TextureParameters},
+    types::{unpack_alignment, ColorSpace, PixelFormat},
+};
+
+const COMPRESSED_RGB_S3TC_DXT1_EXT: GLenum = 0x83F0;
+const COMPRESSED_RGBA_S3TC_DXT1_EXT: GLenum = 0x83F1;
+const COMPRESSED_RGBA_S3TC_DXT3_EXT: GLenum = 0x83F2;
+const COMPRESSED_RGBA_S3TC_DXT5_EXT: GLenum = 0x83F3;
+const COMPRESSED_SRGB_S3TC_DXT1_EXT: GLenum = 0x8C4C;
+const COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT: GLenum = 0x8C4D;
+const COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT: GLenum = 0x8C4E;
+const COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT: GLenum = 0x8C4F;
+
+const DDS_MAGIC: &[u8] = b"DDS ";
+const KTX2_MAGIC: &[u8] = b"\xABKTX 20\xBB\r\n\x1A\n";
+
+/// Block compressed formats readable from KTX2 and DDS containers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressedFormat {
+    Bc1,
+    Bc1Alpha,
+    Bc2,
+    Bc3,
+    Bc4,
+    Bc5,
+    Bc6hUnsigned,
+    Bc6hSigned,
+    Bc7,
+    Etc2Rgb,
+    Etc2Rgba,
+}
+
+impl CompressedFormat {
+    pub fn block_bytes(&self) -> usize {
+        match self {
+            CompressedFormat::Bc1
+            | CompressedFormat::Bc1Alpha
+            | CompressedFormat::Bc4
+            | CompressedFormat::Etc2Rgb => 8,
+            _ => 16,
+        }
+    }
+
+    /// Size of a `width` by `height` level, made of 4x4 blocks.
+    pub fn level_size(&self, width: u32, height: u32) -> usize {
+        let blocks_x = width.div_ceil(4).max(1) as usize;
+        let blocks_y = height.div_ceil(4).max(1) as usize;
+        blocks_x * blocks_y * self.block_bytes()
+    }
+
+    pub fn internal_format(&self, color_space: ColorSpace) -> GLenum {
+        let srgb = color_space == ColorSpace::Srgb;
+        match (self, srgb) {
+            (CompressedFormat::Bc1, false) => COMPRESSED_RGB_S3TC_DXT1_EXT,
+            (CompressedFormat::Bc1, true) => COMPRESSED_SRGB_S3TC_DXT1_EXT,
+            (CompressedFormat::Bc1Alpha, false) => COMPRESSED_RGBA_S3TC_DXT1_EXT,
+            (CompressedFormat::Bc1Alpha, true) => COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT,
+            (CompressedFormat::Bc2, false) => COMPRESSED_RGBA_S3TC_DXT3_EXT,
+            (CompressedFormat::Bc2, true) => COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT,
+            (CompressedFormat::Bc3, false) => COMPRESSED_RGBA_S3TC_DXT5_EXT,
+            (CompressedFormat::Bc3, true) => COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT,
+            (CompressedFormat::Bc4, _) => gl::COMPRESSED_RED_RGTC1,
+            (CompressedFormat::Bc5, _) => gl::COMPRESSED_RG_RGTC2,
+            (CompressedFormat::Bc6hUnsigned, _) => gl::COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT,
+            (CompressedFormat::Bc6hSigned, _) => gl::COMPRESSED_RGB_BPTC_SIGNED_FLOAT,
+            (CompressedFormat::Bc7, false) => gl::COMPRESSED_RGBA_BPTC_UNORM,
+            (CompressedFormat::Bc7, true) => gl::COMPRESSED_SRGB_ALPHA_BPTC_UNORM,
+            (CompressedFormat::Etc2Rgb, false) => gl::COMPRESSED_RGB8_ETC2,
+            (CompressedFormat::Etc2Rgb, true) => gl::COMPRESSED_SRGB8_ETC2,
+            (CompressedFormat::Etc2Rgba, false) => gl::COMPRESSED_RGBA8_ETC2_EAC,
+            (CompressedFormat::Etc2Rgba, true) => gl::COMPRESSED_SRGB8_ALPHA8_ETC2_EAC,
+        }
+    }
+
+    /// Whether the current driver can sample the format directly in `color_space`.
+    pub fn is_supported(&self, color_space: ColorSpace) -> bool {
+        match self {
+            CompressedFormat::Bc1
+            | CompressedFormat::Bc1Alpha
+            | CompressedFormat::Bc2
+            | CompressedFormat::Bc3 => {
+                // The sRGB S3TC formats come from a separate extension.
+                has_extension("GL_EXT_texture_compression_s3tc")
+                    && (color_space == ColorSpace::Linear || has_extension("GL_EXT_texture_sRGB"))
+            }
+            CompressedFormat::Bc4 | CompressedFormat::Bc5 => true,
+            CompressedFormat::Bc6hUnsigned
+            | CompressedFormat::Bc6hSigned
+            | CompressedFormat::Bc7 => {
+                gl_version() >= (4, 2) || has_extension("GL_ARB_texture_compression_bptc")
+            }
+            CompressedFormat::Etc2Rgb | CompressedFormat::Etc2Rgba => {
+                gl_version() >= (4, 3) || has_extension("GL_ARB_ES3_compatibility")
+            }
+        }
+    }
+
+    /// Only BC1 to BC5 have a CPU decoder, BC6H, BC7 and ETC2 need driver support.
+    pub fn can_decode(&self) -> bool {
+        matches!(
+            self,
+            CompressedFormat::Bc1
+                | CompressedFormat::Bc1Alpha
+                | CompressedFormat::Bc2
+                | CompressedFormat::Bc3
+                | CompressedFormat::Bc4
+                | CompressedFormat::Bc5
+        )
+    }
+}
+
+fn gl_version() -> (i32, i32) {
+    let (mut major, mut minor) = (0, 0);
+    unsafe {
+        gl::GetIntegerv(gl::MAJOR_VERSION, &mut major);
+        gl::GetIntegerv(gl::MINOR_VERSION, &mut minor);
+    }
+    (major, minor)
+}
+
+impl Texture2D {
+    /// Loads a pre-compressed texture, mip chain included, from a KTX2 or DDS file.
+    pub fn load_compressed<P>(file_name: P) -> Result<BuilderCompressedTexture2D, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let bytes = fs::read(file_name).map_err(Error::Io)?;
+
+        if bytes.starts_with(KTX2_MAGIC) {
+            parse_ktx2(&bytes)
+        } else if bytes.starts_with(DDS_MAGIC) {
+            parse_dds(&bytes)
+        } else {
+            Err(Error::Container(
+                "Unknown container, expected KTX2 or DDS".into(),
+            ))
+        }
+    }
+}
+
+fn parse_ktx2(bytes: &[u8]) -> Result<BuilderCompressedTexture2D, Error> {
+    let reader = ktx2::Reader::new(bytes).map_err(|e| Error::Container(format!("{e:?}")))?;
+    let header = reader.header();
+
+    if let Some(scheme) = header.supercompression_scheme {
+        return Err(Error::UnsupportedFormat(format!(
+            "KTX2 supercompression {scheme:?}"
+        )));
+    }
+    if header.face_count > 1 || header.layer_count > 1 || header.pixel_depth > 1 {
+        return Err(Error::Container(
+            "Only single 2D images are supported".into(),
+        ));
+    }
+
+    let (format, color_space) = match header.format {
+        Some(Format::BC1_RGB_UNORM_BLOCK) => (CompressedFormat::Bc1, ColorSpace::Linear),
+        Some(Format::BC1_RGB_SRGB_BLOCK) => (CompressedFormat::Bc1, ColorSpace::Srgb),
+        Some(Format::BC1_RGBA_UNORM_BLOCK) => (CompressedFormat::Bc1Alpha, ColorSpace::Linear),
+        Some(Format::BC1_RGBA_SRGB_BLOCK) => (CompressedFormat::Bc1Alpha, ColorSpace::Srgb),
+        Some(Format::BC2_UNORM_BLOCK) => (CompressedFormat::Bc2, ColorSpace::Linear),
+        Some(Format::BC2_SRGB_BLOCK) => (CompressedFormat::Bc2, ColorSpace::Srgb),
+        Some(Format::BC3_UNORM_BLOCK) => (CompressedFormat::Bc3, ColorSpace::Linear),
+        Some(Format::BC3_SRGB_BLOCK) => (CompressedFormat::Bc3, ColorSpace::Srgb),
+        Some(Format::BC4_UNORM_BLOCK) => (CompressedFormat::Bc4, ColorSpace::Linear),
+        Some(Format::BC5_UNORM_BLOCK) => (CompressedFormat::Bc5, ColorSpace::Linear),
+        Some(Format::BC6H_UFLOAT_BLOCK) => (CompressedFormat::Bc6hUnsigned, ColorSpace::Linear),
+        Some(Format::BC6H_SFLOAT_BLOCK) => (CompressedFormat::Bc6hSigned, ColorSpace::Linear),
+        Some(Format::BC7_UNORM_BLOCK) => (CompressedFormat::Bc7, ColorSpace::Linear),
+        Some(Format::BC7_SRGB_BLOCK) => (CompressedFormat::Bc7, ColorSpace::Srgb),
+        Some(Format::ETC2_R8G8B8_UNORM_BLOCK) => (CompressedFormat::Etc2Rgb, ColorSpace::Linear),
+        Some(Format::ETC2_R8G8B8_SRGB_BLOCK) => (CompressedFormat::Etc2Rgb, ColorSpace::Srgb),
+        Some(Format::ETC2_R8G8B8A8_UNORM_BLOCK) => (CompressedFormat::Etc2Rgba, ColorSpace::Linear),
+        Some(Format::ETC2_R8G8B8A8_SRGB_BLOCK) => (CompressedFormat::Etc2Rgba, ColorSpace::Srgb),
+        other => return Err(Error::UnsupportedFormat(format!("KTX2 format {other:?}"))),
+    };
+
+    let levels = reader.levels().map(|level| level.data.to_vec()).collect();
+
+    Ok(BuilderCompressedTexture2D::new(
+        format,
+        (header.pixel_width, header.pixel_height),
+        levels,
+        color_space,
+    ))
+}
+
+fn parse_dds(bytes: &[u8]) -> Result<BuilderCompressedTexture2D, Error> {
+    let dds = Dds::read(bytes).map_err(|e| Error::Container(e.to_string()))?;
+
+    let (format, color_space) = match (dds.get_dxgi_format(), dds.get_d3d_format()) {
+        (Some(DxgiFormat::BC1_UNorm), _) => (CompressedFormat::Bc1Alpha, ColorSpace::Linear),
+        (Some(DxgiFormat::BC1_UNorm_sRGB), _) => (CompressedFormat::Bc1Alpha, ColorSpace::Srgb),
+        (Some(DxgiFormat::BC2_UNorm), _) => (CompressedFormat::Bc2, ColorSpace::Linear),
+        (Some(DxgiFormat::BC2_UNorm_sRGB), _) => (CompressedFormat::Bc2, ColorSpace::Srgb),
+        (Some(DxgiFormat::BC3_UNorm), _) => (CompressedFormat::Bc3, ColorSpace::Linear),
+        (Some(DxgiFormat::BC3_UNorm_sRGB), _) => (CompressedFormat::Bc3, ColorSpace::Srgb),
+        (Some(DxgiFormat::BC4_UNorm), _) => (CompressedFormat::Bc4, ColorSpace::Linear),
+        (Some(DxgiFormat::BC5_UNorm), _) => (CompressedFormat::Bc5, ColorSpace::Linear),
+        (Some(DxgiFormat::BC6H_UF16), _) => (CompressedFormat::Bc6hUnsigned, ColorSpace::Linear),
+        (Some(DxgiFormat::BC6H_SF16), _) => (CompressedFormat::Bc6hSigned, ColorSpace::Linear),
+        (Some(DxgiFormat::BC7_UNorm), _) => (CompressedFormat::Bc7, ColorSpace::Linear),
+        (Some(DxgiFormat::BC7_UNorm_sRGB), _) => (CompressedFormat::Bc7, ColorSpace::Srgb),
+        // Legacy headers carry no color space, these are almost always color art.
+        (None, Some(D3DFormat::DXT1)) => (CompressedFormat::Bc1Alpha, ColorSpace::Srgb),
+        (None, Some(D3DFormat::DXT3)) => (CompressedFormat::Bc2, ColorSpace::Srgb),
+        (None, Some(D3DFormat::DXT5)) => (CompressedFormat::Bc3, ColorSpace::Srgb),
+        (dxgi, d3d) => {
+            return Err(Error::UnsupportedFormat(format!(
+                "DDS format {dxgi:?} / {d3d:?}"
+            )))
+        }
+    };
+
+    let (width, height) = (dds.get_width(), dds.get_height());
+    let mut data = dds
+        .get_data(0)
+        .map_err(|e| Error::Container(e.to_string()))?;
+    let mut levels = vec![];
+    for level in 0..dds.get_num_mipmap_levels().max(1) {
+        let size = format.level_size((width >> level).max(1), (height >> level).max(1));
+        if data.len() < size {
+            return Err(Error::Container("Truncated mip chain".into()));
+        }
+        let (level, rest) = data.split_at(size);
+        levels.push(level.to_vec());
+        data = rest;
+    }
+
+    Ok(BuilderCompressedTexture2D::new(
+        format,
+        (width, height),
+        levels,
+        color_space,
+    ))
+}
+
+#[derive(Debug, Clone)]
+pub struct BuilderCompressedTexture2D {
+    format: CompressedFormat,
+    dimensions: (u32, u32),
+    levels: Vec<Vec<u8>>,
+    parameters: TextureParameters,
+}
+
+impl BuilderCompressedTexture2D {
+    fn new(
+        format: CompressedFormat,
+        dimensions: (u32, u32),
+        levels: Vec<Vec<u8>>,
+        color_space: ColorSpace,
+    ) -> Self {
+        Self {
+            format,
+            dimensions,
+            levels,
+            parameters: TextureParameters {
+                color_space,
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn format(&self) -> CompressedFormat {
+        self.format
+    }
+
+    pub fn levels(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// The color space defaults to what the container declares.
+    pub fn set_parameters(mut self, parameters: TextureParameters) -> Self {
+        self.parameters = parameters;
+        self
+    }
+
+    /// Uploads the blocks as is when the driver supports the format,
+    /// otherwise decodes them to RGBA8 on the CPU.
+    ///
+    /// Fails with [`Error::UnsupportedFormat`] for BC6H, BC7 and ETC2 textures
+    /// the driver can't sample, those have no CPU decoder.
+    pub fn process(self) -> Result<Texture2D, Error> {
+        let Self {
+            format,
+            dimensions: (width, height),
+            levels,
+            parameters,
+        } = self;
+
+        let supported = format.is_supported(parameters.color_space);
+        if !supported && !format.can_decode() {
+            return Err(Error::UnsupportedFormat(format!(
+                "{format:?} is not supported by the driver and can't be decoded"
+            )));
+        }
+
+        let rgba = PixelFormat::new(gl::RGBA8, gl::RGBA, gl::UNSIGNED_BYTE)
+            .with_color_space(parameters.color_space);
+
+        unsafe {
+            let mut id: GLuint = 0;
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_2D, id);
+
+            parameters.apply(gl::TEXTURE_2D);
+
+            for (level, data) in levels.iter().enumerate() {
+                let width = (width >> level).max(1);
+                let height = (height >> level).max(1);
+
+                if supported {
+                    gl::CompressedTexImage2D(
+                        gl::TEXTURE_2D,
+                        level as _,
+                        format.internal_format(parameters.color_space),
+                        width as _,
+                        height as _,
+                        0,
+                        data.len() as _,
+                        data.as_ptr().cast(),
+                    );
+                } else {
+                    let pixels = decode(format, width, height, data)?;
+                    gl::PixelStorei(gl::UNPACK_ALIGNMENT, unpack_alignment(width as usize * 4));
+                    gl::TexImage2D(
+                        gl::TEXTURE_2D,
+                        level as _,
+                        rgba.internal_format as _,
+                        width as _,
+                        height as _,
+                        0,
+                        rgba.format,
+                        rgba.data_type,
+                        pixels.as_ptr().cast(),
+                    );
+                    gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+                }
+            }
+
+            // Compressed formats can't have their mip chain generated, use what the file ships.
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_MAX_LEVEL,
+                levels.len() as i32 - 1,
+            );
+
+            // Decoded levels can be updated like any RGBA8 texture, blocks can't.
+            Ok(Texture2D::from_id(
+                id,
+                (width, height),
+                (!supported).then_some(rgba),
+                levels.len() > 1,
+            ))
+        }
+    }
+}
+
+/// Decodes a whole level to tightly packed RGBA8.
+///
+/// Fails with [`Error::UnsupportedFormat`] for formats [`CompressedFormat::can_decode`] rejects.
+pub fn decode(
+    format: CompressedFormat,
+    width: u32,
+    height: u32,
+    data: &[u8],
+) -> Result<Vec<u8>, Error> {
+    if !format.can_decode() {
+        return Err(Error::UnsupportedFormat(format!(
+            "{format:?} has no CPU decoder"
+        )));
+    }
+
+    let (width, height) = (width as usize, height as usize);
+    let blocks_x = width.div_ceil(4).max(1);
+    let mut pixels = vec![0u8; width * height * 4];
+
+    data.chunks_exact(format.block_bytes())
+        .enumerate()
+        .for_each(|(index, block)| {
+            let texels = decode_block(format, block);
+            let (bx, by) = (index % blocks_x * 4, index / blocks_x * 4);
+
+            for (i, texel) in texels.iter().enumerate() {
+                let (x, y) = (bx + i % 4, by + i / 4);
+                if x < width && y < height {
+                    let offset = (y * width + x) * 4;
+                    pixels[offset..offset + 4].copy_from_slice(texel);
+                }
+            }
+        });
+
+    Ok(pixels)
+}
+
+fn decode_block(format: CompressedFormat, block: &[u8]) -> [[u8; 4]; 16] {
+    match format {
+        CompressedFormat::Bc1 => decode_bc1(block, false, false),
+        CompressedFormat::Bc1Alpha => decode_bc1(block, true, false),
+        CompressedFormat::Bc2 => {
+            let mut texels = decode_bc1(&block[8..], false, true);
+            texels.iter_mut().enumerate().for_each(|(i, texel)| {
+                let nibble = (block[i / 2] >> (4 * (i % 2))) & 0xF;
+                texel[3] = nibble * 17;
+            });
+            texels
+        }
+        CompressedFormat::Bc3 => {
+            let mut texels = decode_bc1(&block[8..], false, true);
+            let alpha = decode_bc4(&block[..8]);
+            texels
+                .iter_mut()
+                .zip(alpha)
+                .for_each(|(texel, a)| texel[3] = a);
+            texels
+        }
+        CompressedFormat::Bc4 => decode_bc4(block).map(|r| [r, 0, 0, 255]),
+        CompressedFormat::Bc5 => {
+            let red = decode_bc4(&block[..8]);
+            let green = decode_bc4(&block[8..]);
+            let mut texels = [[0, 0, 0, 255]; 16];
+            texels.iter_mut().enumerate().for_each(|(i, texel)| {
+                texel[0] = red[i];
+                texel[1] = green[i];
+            });
+            texels
+        }
+        _ => unreachable!("{format:?} has no CPU decoder"),
+    }
+}
+
+/// `alpha` enables BC1 punch-through, `four_color` forces the mode used inside BC2/BC3.
+fn decode_bc1(block: &[u8], alpha: bool, four_color: bool) -> [[u8; 4]; 16] {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+
+    let expand = |c: u16| -> [u32; 3] {
+        let r = (c >> 11) as u32 & 0x1F;
+        let g = (c >> 5) as u32 & 0x3F;
+        let b = c as u32 & 0x1F;
+        [
+            (r << 3) | (r >> 2),
+            (g << 2) | (g >> 4),
+            (b << 3) | (b >> 2),
+        ]
+    };
+    let (e0, e1) = (expand(c0), expand(c1));
+    let mix = |w0: u32, w1: u32, d: u32| -> [u8; 4] {
+        let c = [0, 1, 2].map(|i| ((e0[i] * w0 + e1[i] * w1) / d) as u8);
+        [c[0], c[1], c[2], 255]
+    };
+
+    let palette = if c0 > c1 || four_color {
+        [mix(1, 0, 1), mix(0, 1, 1), mix(2, 1, 3), mix(1, 2, 3)]
+    } else {
+        let transparent = if alpha { [0, 0, 0, 0] } else { [0, 0, 0, 255] };
+        [mix(1, 0, 1), mix(0, 1, 1), mix(1, 1, 2), transparent]
+    };
+
+    std::array::from_fn(|i| palette[(indices >> (2 * i)) as usize & 0x3])
+}
+
+fn decode_bc4(block: &[u8]) -> [u8; 16] {
+    let (r0, r1) = (block[0] as u32, block[1] as u32);
+    let palette: [u8; 8] = if r0 > r1 {
+        std::array::from_fn(|i| match i {
+            0 => r0 as u8,
+            1 => r1 as u8,
+            i => ((r0 * (8 - i as u32) + r1 * (i as u32 - 1)) / 7) as u8,
+        })
+    } else {
+        std::array::from_fn(|i| match i {
+            0 => r0 as u8,
+            1 => r1 as u8,
+            6 => 0,
+            7 => 255,
+            i => ((r0 * (6 - i as u32) + r1 * (i as u32 - 1)) / 5) as u8,
+        })
+    };
+
+    let bits = block[2..8]
+        .iter()
+        .rev()
+        .fold(0u64, |bits, byte| (bits << 8) | *byte as u64);
+
+    std::array::from_fn(|i| palette[(bits >> (3 * i)) as usize & 0x7])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Red and blue endpoints, texel `i` uses index `i % 4`.
+    const RED_BLUE: [u8; 8] = [0x00, 0xF8, 0x1F, 0x00, 0xE4, 0xE4, 0xE4, 0xE4];
+    /// Endpoints 255 and 0, texel `i` uses index `i % 8`.
+    const RAMP: [u8; 8] = [255, 0, 0x88, 0xC6, 0xFA, 0x88, 0xC6, 0xFA];
+    const RAMP_VALUES: [u8; 8] = [255, 0, 218, 182, 145, 109, 72, 36];
+
+    #[test]
+    fn bc1_four_color_block() {
+        let texels = decode_block(CompressedFormat::Bc1, &RED_BLUE);
+        let palette = [
+            [255, 0, 0, 255],
+            [0, 0, 255, 255],
+            [170, 0, 85, 255],
+            [85, 0, 170, 255],
+        ];
+        for (i, texel) in texels.iter().enumerate() {
+            assert_eq!(*texel, palette[i % 4]);
+        }
+    }
+
+    #[test]
+    fn bc1_three_color_block_punches_through_with_alpha() {
+        // Swapped endpoints select the three color mode.
+        let block = [0x1F, 0x00, 0x00, 0xF8, 0xE4, 0xE4, 0xE4, 0xE4];
+
+        let opaque = decode_block(CompressedFormat::Bc1, &block);
+        assert_eq!(opaque[2], [127, 0, 127, 255]);
+        assert_eq!(opaque[3], [0, 0, 0, 255]);
+
+        let alpha = decode_block(CompressedFormat::Bc1Alpha, &block);
+        assert_eq!(alpha[3], [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn bc2_explicit_alpha() {
+        let mut block = [0xF0; 16];
+        block[8..].copy_from_slice(&RED_BLUE);
+
+        let texels = decode_block(CompressedFormat::Bc2, &block);
+        assert_eq!(texels[0], [255, 0, 0, 0]);
+        assert_eq!(texels[1], [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn bc3_interpolated_alpha() {
+        let mut block = [0; 16];
+        block[..8].copy_from_slice(&RAMP);
+        block[8..].copy_from_slice(&RED_BLUE);
+
+        let texels = decode_block(CompressedFormat::Bc3, &block);
+        for (i, texel) in texels.iter().enumerate() {
+            assert_eq!(texel[3], RAMP_VALUES[i % 8]);
+        }
+        assert_eq!(texels[2][..3], [170, 0, 85]);
+    }
+
+    #[test]
+    fn bc4_eight_and_six_value_modes() {
+        let texels = decode_block(CompressedFormat::Bc4, &RAMP);
+        for (i, texel) in texels.iter().enumerate() {
+            assert_eq!(*texel, [RAMP_VALUES[i % 8], 0, 0, 255]);
+        }
+
+        let mut block = RAMP;
+        block[..2].copy_from_slice(&[0, 255]);
+        let values = [0, 255, 51, 102, 153, 204, 0, 255];
+        let texels = decode_block(CompressedFormat::Bc4, &block);
+        for (i, texel) in texels.iter().enumerate() {
+            assert_eq!(texel[0], values[i % 8]);
+        }
+    }
+
+    #[test]
+    fn bc5_red_and_green_channels() {
+        let mut block = [0; 16];
+        block[..8].copy_from_slice(&RAMP);
+        block[8..10].copy_from_slice(&[7, 7]);
+
+        let texels = decode_block(CompressedFormat::Bc5, &block);
+        for (i, texel) in texels.iter().enumerate() {
+            assert_eq!(*texel, [RAMP_VALUES[i % 8], 7, 0, 255]);
+        }
+    }
+
+    #[test]
+    fn decode_clips_partial_blocks() {
+        let pixels = decode(CompressedFormat::Bc1, 2, 2, &RED_BLUE).unwrap();
+        assert_eq!(
+            pixels,
+            [
+                [255, 0, 0, 255],
+                [0, 0, 255, 255],
+                [255, 0, 0, 255],
+                [0, 0, 255, 255]
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn decode_rejects_formats_without_decoder() {
+        let data = [0; 16];
+        for format in [
+            CompressedFormat::Bc6hUnsigned,
+            CompressedFormat::Bc7,
+            CompressedFormat::Etc2Rgba,
+        ] {
+            assert!(matches!(
+                decode(format, 4, 4, &data),
+                Err(Error::UnsupportedFormat(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use crate::object::components::Transform;
+
+use super::{
+    math::{add, cross, dot, length, mul, normalize, sub},
+    shadows::CameraView,
+    types::Vec3,
+};
+
+/// Axis-aligned box around a mesh's vertices, in model space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Bounds {
+    /// An empty set of points gets a zero-sized box at the origin.
+    pub fn from_points<I: IntoIterator<Item = Vec3>>(points: I) -> Self {
+        let mut points = points.into_iter();
+        let Some(first) = points.next() else {
+            return Self {
+                min: Vec3::new(0., 0., 0.),
+                max: Vec3::new(0., 0., 0.),
+            };
+        };
+
+        points.fold(
+            Self {
+                min: first,
+                max: first,
+            },
+            |bounds, p| Self {
+                min: Vec3::new(
+                    bounds.min.x.min(p.x),
+                    bounds.min.y.min(p.y),
+                    bounds.min.z.min(p.z),
+                ),
+                max: Vec3::new(
+                    bounds.max.x.max(p.x),
+                    bounds.max.y.max(p.y),
+                    bounds.max.z.max(p.z),
+                ),
+            },
+        )
+    }
+
+    pub fn center(&self) -> Vec3 {
+        mul(add(self.min, self.max), 0.5)
+    }
+
+    /// Sphere through the box's corners.
+    pub fn sphere(&self) -> BoundingSphere {
+        BoundingSphere {
+            center: self.center(),
+            radius: length(sub(self.max, self.min)) / 2.,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    /// World space sphere of a model space one. It's centered on the object's origin
+    /// and reaches as far as the model sphere can under any rotation, so it never
+    /// depends on the rotation order.
+    pub fn transformed(&self, transform: &Transform) -> BoundingSphere {
+        let Vec3 { x, y, z } = transform.scale;
+        let offset = Vec3::new(self.center.x * x, self.center.y * y, self.center.z * z);
+        let max_scale = x.abs().max(y.abs()).max(z.abs());
+
+        BoundingSphere {
+            center: transform.pos,
+            radius: length(offset) + self.radius * max_scale,
+        }
+    }
+}
+
+/// Plane through `point` with an inward facing unit `normal`.
+#[derive(Debug, Clone, Copy)]
+struct Plane {
+    normal: Vec3,
+    distance: f32,
+}
+
+impl Plane {
+    fn new(normal: Vec3, point: Vec3) -> Self {
+        let normal = normalize(normal);
+        Self {
+            normal,
+            distance: dot(normal, point),
+        }
+    }
+
+    fn signed_distance(&self, point: Vec3) -> f32 {
+        dot(self.normal, point) - self.distance
+    }
+}
+
+/// Volume the camera sees, bounded by six planes.
+#[derive(Debug, Clone, Copy)]
+pub struct Frustum {
+    planes: [Plane; 6],
+}
+
+impl Frustum {
+    pub fn new(camera: &CameraView) -> Self {
+        let forward = normalize(sub(camera.target, camera.position));
+        let right = normalize(cross(forward, camera.up));
+        let up = cross(right, forward);
+        let tan_half_fov = (camera.fov.to_radians() / 2.).tan();
+        let (h, w) = (tan_half_fov, tan_half_fov * camera.aspect);
+
+        // Side planes go through the eye, their normals tilted inwards by the half angles.
+        let eye = camera.position;
+        Self {
+            planes: [
+                Plane::new(forward, add(eye, mul(forward, camera.near))),
+                Plane::new(mul(forward, -1.), add(eye, mul(forward, camera.far))),
+                Plane::new(add(mul(forward, w), right), eye),
+                Plane::new(sub(mul(forward, w), right), eye),
+                Plane::new(add(mul(forward, h), up), eye),
+                Plane::new(sub(mul(forward, h), up), eye),
+            ],
+        }
+    }
+
+    /// Whether any part of `sphere` may be visible.
+    pub fn intersects(&self, sphere: &BoundingSphere) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(sphere.center) >= -sphere.radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Looking down -Z from the origin, 90 degrees both ways.
+    fn frustum() -> Frustum {
+        Frustum::new(&CameraView {
+            position: Vec3::new(0., 0., 0.),
+            target: Vec3::new(0., 0., -1.),
+            up: Vec3::new(0., 1., 0.),
+            fov: 90.,
+            aspect: 1.,
+            near: 0.1,
+            far: 100.,
+        })
+    }
+
+    fn sphere(x: f32, y: f32, z: f32, radius: f32) -> BoundingSphere {
+        BoundingSphere {
+            center: Vec3::new(x, y, z),
+            radius,
+        }
+    }
+
+    #[test]
+    fn frustum_keeps_spheres_in_view() {
+        assert!(frustum().intersects(&sphere(0., 0., -10., 1.)));
+        assert!(frustum().intersects(&sphere(5., -5., -50., 0.5)));
+    }
+
+    #[test]
+    fn frustum_rejects_spheres_behind_the_camera() {
+        assert!(!frustum().intersects(&sphere(0., 0., 10., 1.)));
+        // Reaching past the near plane is enough.
+        assert!(frustum().intersects(&sphere(0., 0., 0.5, 1.)));
+    }
+
+    #[test]
+    fn frustum_rejects_spheres_beyond_the_far_plane() {
+        assert!(!frustum().intersects(&sphere(0., 0., -102., 1.)));
+        assert!(frustum().intersects(&sphere(0., 0., -100.5, 1.)));
+    }
+
+    #[test]
+    fn frustum_keeps_spheres_straddling_a_side_plane() {
+        // The right plane passes through x = 10 at this depth.
+        assert!(frustum().intersects(&sphere(10.5, 0., -10., 1.)));
+        assert!(!frustum().intersects(&sphere(12., 0., -10., 1.)));
+        assert!(!frustum().intersects(&sphere(0., -12., -10., 1.)));
+    }
+
+    #[test]
+    fn transformed_sphere_covers_non_uniform_scale() {
+        let transform = Transform {
+            pos: Vec3::new(5., 0., 0.),
+            scale: Vec3::new(2., 1., -3.),
+            ..Transform::default()
+        };
+        let sphere = sphere(1., 0., 0., 1.).transformed(&transform);
+
+        assert_eq!(sphere.center, Vec3::new(5., 0., 0.));
+        // The scaled offset to the center, plus the radius stretched by the largest axis.
+        assert_eq!(sphere.radius, 2. + 3.);
+    }
+
+    #[test]
+    fn bounds_sphere_passes_through_the_corners() {
+        let bounds = Bounds::from_points([Vec3::new(-1., -2., -2.), Vec3::new(1., 2., 2.)]);
+        let sphere = bounds.sphere();
+
+        assert_eq!(sphere.center, Vec3::new(0., 0., 0.));
+        assert_eq!(sphere.radius, 3.);
+    }
+}
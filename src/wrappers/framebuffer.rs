@@ -0,0 +1,391 @@
+use std::fmt::Display;
+
+use gl::types::{GLenum, GLint, GLuint};
+
+use super::{
+    textures::{Texture, Texture2D, TextureParameters},
+    types::PixelFormat,
+};
+
+/// Where an attachment keeps its pixels. Only textures can be sampled afterwards,
+/// renderbuffers are cheaper for depth and stencil that is never read back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttachmentStorage {
+    Texture,
+    Renderbuffer,
+}
+
+#[derive(Debug, Clone)]
+pub struct Renderbuffer {
+    id: GLuint,
+    format: PixelFormat,
+}
+
+impl Renderbuffer {
+    pub fn new(size: (u32, u32), format: PixelFormat) -> Self {
+        let mut id = 0;
+        unsafe { gl::GenRenderbuffers(1, &mut id) };
+
+        let renderbuffer = Self { id, format };
+        renderbuffer.allocate(size);
+        renderbuffer
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    fn allocate(&self, (width, height): (u32, u32)) {
+        unsafe {
+            gl::BindRenderbuffer(gl::RENDERBUFFER, self.id);
+            gl::RenderbufferStorage(
+                gl::RENDERBUFFER,
+                self.format.internal_format,
+                width as _,
+                height as _,
+            );
+            gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Attachment {
+    Texture(Texture2D),
+    Renderbuffer(Renderbuffer),
+}
+
+impl Attachment {
+    fn new(
+        storage: AttachmentStorage,
+        size: (u32, u32),
+        format: PixelFormat,
+        parameters: &TextureParameters,
+    ) -> Self {
+        match storage {
+            AttachmentStorage::Texture => {
+                Attachment::Texture(Texture2D::empty(size, format, parameters.clone()))
+            }
+            AttachmentStorage::Renderbuffer => {
+                Attachment::Renderbuffer(Renderbuffer::new(size, format))
+            }
+        }
+    }
+
+    pub fn texture(&self) -> Option<&Texture2D> {
+        match self {
+            Attachment::Texture(texture) => Some(texture),
+            Attachment::Renderbuffer(_) => None,
+        }
+    }
+
+    fn format(&self) -> PixelFormat {
+        match self {
+            Attachment::Texture(texture) => texture.format().unwrap(),
+            Attachment::Renderbuffer(renderbuffer) => renderbuffer.format,
+        }
+    }
+
+    /// Expects the framebuffer to be bound.
+    unsafe fn attach(&self, point: GLenum) {
+        match self {
+            Attachment::Texture(texture) => {
+                gl::FramebufferTexture2D(gl::FRAMEBUFFER, point, gl::TEXTURE_2D, texture.id(), 0)
+            }
+            Attachment::Renderbuffer(renderbuffer) => gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                point,
+                gl::RENDERBUFFER,
+                renderbuffer.id,
+            ),
+        }
+    }
+
+    fn resize(&mut self, size: (u32, u32)) {
+        let format = self.format();
+        match self {
+            Attachment::Texture(texture) => texture.reallocate(size, format),
+            Attachment::Renderbuffer(renderbuffer) => renderbuffer.allocate(size),
+        }
+    }
+}
+
+/// Render target with any number of named color attachments and
+/// an optional depth, stencil or combined depth-stencil attachment.
+#[derive(Debug, Clone)]
+pub struct Framebuffer {
+    id: GLuint,
+    size: (u32, u32),
+    colors: Vec<(String, Attachment)>,
+    depth_stencil: Option<Attachment>,
+    resize_with_window: bool,
+}
+
+impl Framebuffer {
+    pub fn builder(width: u32, height: u32) -> BuilderFramebuffer {
+        BuilderFramebuffer {
+            size: (width, height),
+            colors: vec![],
+            depth_stencil: None,
+            parameters: TextureParameters::clamped_to_edge().without_mipmaps(),
+            resize_with_window: false,
+        }
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    pub fn resizes_with_window(&self) -> bool {
+        self.resize_with_window
+    }
+
+    /// Binds the framebuffer for drawing and sets the viewport to cover it.
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.id);
+            gl::Viewport(0, 0, self.size.0 as _, self.size.1 as _);
+        }
+    }
+
+    /// Goes back to the default framebuffer, the viewport is left to the caller.
+    pub fn unbind() {
+        unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, 0) };
+    }
+
+    pub fn attachment(&self, name: &str) -> Option<&Attachment> {
+        self.colors
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, attachment)| attachment)
+    }
+
+    pub fn texture(&self, name: &str) -> Option<&Texture2D> {
+        self.attachment(name).and_then(Attachment::texture)
+    }
+
+    /// Color attachments that can be sampled, in attachment order.
+    pub fn textures(&self) -> impl Iterator<Item = (&str, &Texture2D)> {
+        self.colors
+            .iter()
+            .filter_map(|(name, attachment)| Some((name.as_str(), attachment.texture()?)))
+    }
+
+    pub fn depth_texture(&self) -> Option<&Texture2D> {
+        self.depth_stencil.as_ref().and_then(Attachment::texture)
+    }
+
+    /// Reallocates every attachment, their contents are undefined afterwards.
+    /// Texture handles taken before keep working but report the old size.
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<(), Error> {
+        self.size = (width, height);
+        self.colors
+            .iter_mut()
+            .map(|(_, attachment)| attachment)
+            .chain(&mut self.depth_stencil)
+            .for_each(|attachment| attachment.resize((width, height)));
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.id);
+            let status = check_status();
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            status
+        }
+    }
+
+    /// Copies color attachment `index` to the default framebuffer, stretched to `size`.
+    pub fn blit_to_screen(&self, index: usize, (width, height): (i32, i32)) {
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.id);
+            gl::ReadBuffer(gl::COLOR_ATTACHMENT0 + index as u32);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, 0);
+            gl::BlitFramebuffer(
+                0,
+                0,
+                self.size.0 as _,
+                self.size.1 as _,
+                0,
+                0,
+                width,
+                height,
+                gl::COLOR_BUFFER_BIT,
+                gl::LINEAR,
+            );
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BuilderFramebuffer {
+    size: (u32, u32),
+    colors: Vec<(String, PixelFormat, AttachmentStorage)>,
+    depth_stencil: Option<(PixelFormat, AttachmentStorage)>,
+    parameters: TextureParameters,
+    resize_with_window: bool,
+}
+
+impl BuilderFramebuffer {
+    /// Adds a color texture, written by fragment shader output `n` for the `n`-th one added.
+    pub fn add_color<N: Into<String>>(self, name: N, format: PixelFormat) -> Self {
+        self.add_color_attachment(name, format, AttachmentStorage::Texture)
+    }
+
+    pub fn add_color_attachment<N: Into<String>>(
+        mut self,
+        name: N,
+        format: PixelFormat,
+        storage: AttachmentStorage,
+    ) -> Self {
+        self.colors.push((name.into(), format, storage));
+        self
+    }
+
+    /// `format` picks between depth, stencil and combined depth-stencil,
+    /// e.g. [`PixelFormat::DEPTH24_STENCIL8`].
+    pub fn set_depth_stencil(mut self, format: PixelFormat, storage: AttachmentStorage) -> Self {
+        self.depth_stencil = Some((format, storage));
+        self
+    }
+
+    /// Sampling parameters of texture attachments, edge-clamped without mipmaps by default.
+    pub fn set_parameters(mut self, parameters: TextureParameters) -> Self {
+        self.parameters = parameters;
+        self
+    }
+
+    pub fn set_size(mut self, width: u32, height: u32) -> Self {
+        self.size = (width, height);
+        self
+    }
+
+    /// Lets the engine keep the framebuffer the size of the window.
+    pub fn resize_with_window(mut self) -> Self {
+        self.resize_with_window = true;
+        self
+    }
+
+    pub fn build(self) -> Result<Framebuffer, Error> {
+        let Self {
+            size,
+            colors,
+            depth_stencil,
+            parameters,
+            resize_with_window,
+        } = self;
+
+        let max = max_color_attachments();
+        if colors.len() > max {
+            return Err(Error::TooManyColorAttachments {
+                requested: colors.len(),
+                max,
+            });
+        }
+
+        unsafe {
+            let mut id = 0;
+            gl::GenFramebuffers(1, &mut id);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, id);
+
+            let colors: Vec<_> = colors
+                .into_iter()
+                .enumerate()
+                .map(|(i, (name, format, storage))| {
+                    let attachment = Attachment::new(storage, size, format, &parameters);
+                    attachment.attach(gl::COLOR_ATTACHMENT0 + i as u32);
+                    (name, attachment)
+                })
+                .collect();
+
+            let depth_stencil = depth_stencil.map(|(format, storage)| {
+                let attachment = Attachment::new(storage, size, format, &parameters);
+                attachment.attach(depth_stencil_point(format));
+                attachment
+            });
+
+            if colors.is_empty() {
+                gl::DrawBuffer(gl::NONE);
+                gl::ReadBuffer(gl::NONE);
+            } else {
+                let buffers: Vec<_> = (0..colors.len() as u32)
+                    .map(|i| gl::COLOR_ATTACHMENT0 + i)
+                    .collect();
+                gl::DrawBuffers(buffers.len() as _, buffers.as_ptr());
+            }
+
+            let status = check_status();
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            status?;
+
+            Ok(Framebuffer {
+                id,
+                size,
+                colors,
+                depth_stencil,
+                resize_with_window,
+            })
+        }
+    }
+}
+
+fn depth_stencil_point(format: PixelFormat) -> GLenum {
+    match format.format {
+        gl::DEPTH_STENCIL => gl::DEPTH_STENCIL_ATTACHMENT,
+        gl::STENCIL_INDEX => gl::STENCIL_ATTACHMENT,
+        _ => gl::DEPTH_ATTACHMENT,
+    }
+}
+
+fn max_color_attachments() -> usize {
+    let (mut attachments, mut draw_buffers): (GLint, GLint) = (0, 0);
+    unsafe {
+        gl::GetIntegerv(gl::MAX_COLOR_ATTACHMENTS, &mut attachments);
+        gl::GetIntegerv(gl::MAX_DRAW_BUFFERS, &mut draw_buffers);
+    }
+    attachments.min(draw_buffers) as usize
+}
+
+/// Expects the framebuffer to be bound.
+unsafe fn check_status() -> Result<(), Error> {
+    match gl::CheckFramebufferStatus(gl::FRAMEBUFFER) {
+        gl::FRAMEBUFFER_COMPLETE => Ok(()),
+        status => Err(Error::Incomplete(status)),
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Incomplete(GLenum),
+    TooManyColorAttachments { requested: usize, max: usize },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Incomplete(status) => {
+                let reason = match *status {
+                    gl::FRAMEBUFFER_UNDEFINED => "undefined",
+                    gl::FRAMEBUFFER_INCOMPLETE_ATTACHMENT => "incomplete attachment",
+                    gl::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => "missing attachment",
+                    gl::FRAMEBUFFER_INCOMPLETE_DRAW_BUFFER => "incomplete draw buffer",
+                    gl::FRAMEBUFFER_INCOMPLETE_READ_BUFFER => "incomplete read buffer",
+                    gl::FRAMEBUFFER_UNSUPPORTED => "unsupported format combination",
+                    gl::FRAMEBUFFER_INCOMPLETE_MULTISAMPLE => "mismatched multisampling",
+                    gl::FRAMEBUFFER_INCOMPLETE_LAYER_TARGETS => "mismatched layer targets",
+                    _ => "unknown status",
+                };
+                write!(f, "Framebuffer is incomplete: {reason} (0x{status:x})")
+            }
+            Error::TooManyColorAttachments { requested, max } => write!(
+                f,
+                "{requested} color attachments requested, the driver supports {max}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
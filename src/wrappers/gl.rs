@@ -1,7 +1,9 @@
-use std::marker::PhantomData;
+use std::{ffi::CStr, marker::PhantomData};
 
 use glfw::Window;
 
+use super::types::TextureUnit;
+
 pub struct GL {
     _guard: PhantomData<()>,
 }
@@ -9,8 +11,21 @@ pub struct GL {
 impl GL {
     pub fn init(window: &mut Window) -> Self {
         gl::load_with(|s| window.get_proc_address(s) as *const _);
+        TextureUnit::query_max_units();
         Self {
             _guard: PhantomData,
         }
     }
 }
+
+/// Whether the current context advertises the extension `name`.
+pub fn has_extension(name: &str) -> bool {
+    unsafe {
+        let mut count = 0;
+        gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count);
+        (0..count as u32).any(|i| {
+            let extension = gl::GetStringi(gl::EXTENSIONS, i);
+            !extension.is_null() && CStr::from_ptr(extension.cast()).to_bytes() == name.as_bytes()
+        })
+    }
+}
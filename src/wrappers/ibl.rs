@@ -0,0 +1,270 @@
+use std::fmt::Display;
+
+use engine_math::{
+    transform::homogeneous::{lookat3, perspective3},
+    Vector, Vector3,
+};
+use gl::types::{GLenum, GLuint};
+
+use crate::object::components::Transform;
+
+use super::{
+    framebuffer::{self, Framebuffer},
+    gl::GL,
+    mesh::BoundStaticMesh,
+    post_process::fullscreen_shader,
+    shader::{Shader, ShaderSource},
+    skybox,
+    textures::{Texture, Texture2D, TextureCube, TextureParameters},
+    types::{FilterOptions, PixelFormat, Uniform},
+};
+
+const CUBE_VERTEX_SHADER: &str = include_str!("../../shaders/ibl/cube_vertex.glsl");
+const IRRADIANCE: &str = include_str!("../../shaders/ibl/irradiance.glsl");
+const PREFILTER: &str = include_str!("../../shaders/ibl/prefilter.glsl");
+const BRDF: &str = include_str!("../../shaders/ibl/brdf.glsl");
+const PBR_VERTEX_SHADER: &str = include_str!("../../shaders/lit_vertex.glsl");
+const PBR_FRAGMENT_SHADER: &str = include_str!("../../shaders/pbr_fragment.glsl");
+
+/// Names the PBR shader samples the environment under, see [`UnsafeEngine::set_environment`](crate::engine::UnsafeEngine::set_environment).
+pub const IRRADIANCE_MAP: &str = "ibl.irradiance";
+pub const PREFILTERED_MAP: &str = "ibl.prefiltered";
+pub const BRDF_LUT: &str = "ibl.brdf_lut";
+
+const IRRADIANCE_SIZE: u32 = 32;
+const PREFILTERED_SIZE: u32 = 128;
+/// Roughness steps stored in the prefiltered map's mip chain.
+/// Must match `PREFILTERED_LEVELS` in the PBR shader.
+pub const PREFILTERED_LEVELS: u32 = 5;
+const BRDF_LUT_SIZE: u32 = 512;
+
+/// Built-in Cook-Torrance shader for metallic-roughness materials,
+/// configure it through [`PbrMaterial`](super::material::PbrMaterial).
+pub fn pbr_shader() -> ShaderSource {
+    ShaderSource::from_strings(
+        PBR_VERTEX_SHADER.to_string(),
+        PBR_FRAGMENT_SHADER.to_string(),
+    )
+}
+
+/// Lighting precomputed from an environment cube map.
+#[derive(Debug, Clone)]
+pub struct Environment {
+    /// Cosine-weighted diffuse lighting per normal direction.
+    pub irradiance: TextureCube,
+    /// Specular reflections, one mip level per roughness step.
+    pub prefiltered: TextureCube,
+}
+
+/// Convolves environment cube maps for image-based lighting.
+#[derive(Debug)]
+pub struct IblBaker {
+    irradiance: Shader,
+    prefilter: Shader,
+    brdf: Shader,
+    cube: BoundStaticMesh,
+    fbo: GLuint,
+    vao: GLuint,
+}
+
+impl IblBaker {
+    pub fn new(gl: &GL) -> Self {
+        let compile = |fragment: &str| {
+            ShaderSource::from_strings(CUBE_VERTEX_SHADER.to_string(), fragment.to_string())
+                .compile(gl)
+        };
+        let irradiance = compile(IRRADIANCE);
+        let prefilter = compile(PREFILTER);
+        let brdf = fullscreen_shader(BRDF.to_string()).compile(gl);
+        let cube = skybox::cube().create_static(&irradiance);
+
+        let (mut fbo, mut vao) = (0, 0);
+        unsafe {
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::GenVertexArrays(1, &mut vao);
+        }
+
+        Self {
+            irradiance,
+            prefilter,
+            brdf,
+            cube,
+            fbo,
+            vao,
+        }
+    }
+
+    /// `environment` must be a cube map, linear HDR for best results.
+    /// Mipmaps are generated on it to reduce sampling noise, its filtering is left as it was.
+    pub fn bake(&self, environment: &dyn Texture) -> Result<Environment, Error> {
+        if environment.target() != gl::TEXTURE_CUBE_MAP {
+            return Err(Error::NotACubeMap(environment.target()));
+        }
+
+        let parameters = TextureParameters {
+            min_filter: FilterOptions::LinearMipmapLinear,
+            ..TextureParameters::cube_map()
+        };
+        let irradiance = TextureCube::empty(
+            IRRADIANCE_SIZE,
+            PixelFormat::RGBA16F,
+            1,
+            TextureParameters::cube_map(),
+        );
+        let prefiltered = TextureCube::empty(
+            PREFILTERED_SIZE,
+            PixelFormat::RGBA16F,
+            PREFILTERED_LEVELS,
+            parameters,
+        );
+
+        let mut min_filter = 0;
+        unsafe {
+            environment.bind(0.into());
+            gl::GetTexParameteriv(
+                gl::TEXTURE_CUBE_MAP,
+                gl::TEXTURE_MIN_FILTER,
+                &mut min_filter,
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_CUBE_MAP,
+                gl::TEXTURE_MIN_FILTER,
+                gl::LINEAR_MIPMAP_LINEAR as _,
+            );
+            gl::GenerateMipmap(gl::TEXTURE_CUBE_MAP);
+        }
+
+        self.begin();
+
+        self.irradiance
+            .bind_textures([("environmentMap", environment)]);
+        self.render_faces(&self.irradiance, &irradiance, IRRADIANCE_SIZE, 0);
+
+        self.prefilter
+            .bind_textures([("environmentMap", environment)]);
+        for level in 0..PREFILTERED_LEVELS {
+            let roughness = level as f32 / (PREFILTERED_LEVELS - 1) as f32;
+            self.prefilter
+                .set_uniform("roughness", Uniform::Float(roughness));
+            self.render_faces(
+                &self.prefilter,
+                &prefiltered,
+                (PREFILTERED_SIZE >> level).max(1),
+                level,
+            );
+        }
+
+        self.end();
+
+        unsafe {
+            environment.bind(0.into());
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MIN_FILTER, min_filter);
+        }
+
+        Ok(Environment {
+            irradiance,
+            prefiltered,
+        })
+    }
+
+    /// Split-sum scale and bias to F0, only depends on the BRDF so it's computed once.
+    /// Fails if the driver can't render to RG16F.
+    pub fn brdf_lut(&self) -> Result<Texture2D, Error> {
+        let parameters = TextureParameters {
+            mag_fiter: FilterOptions::Linear,
+            min_filter: FilterOptions::Linear,
+            ..TextureParameters::clamped_to_edge()
+        };
+        let target = Framebuffer::builder(BRDF_LUT_SIZE, BRDF_LUT_SIZE)
+            .add_color(BRDF_LUT, PixelFormat::RG16F)
+            .set_parameters(parameters.without_mipmaps())
+            .build()
+            .map_err(Error::Framebuffer)?;
+
+        self.begin();
+        target.bind();
+        self.brdf.use_program();
+        unsafe {
+            gl::BindVertexArray(self.vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+        }
+        self.end();
+
+        Ok(target.texture(BRDF_LUT).unwrap().clone())
+    }
+
+    fn begin(&self) {
+        unsafe { gl::Disable(gl::DEPTH_TEST) };
+    }
+
+    /// Restores the default framebuffer and the state [`Self::begin`] changed.
+    /// The caller resets the viewport.
+    fn end(&self) {
+        unsafe {
+            Framebuffer::unbind();
+            gl::Enable(gl::DEPTH_TEST);
+        }
+    }
+
+    fn render_faces(&self, shader: &Shader, target: &TextureCube, size: u32, level: u32) {
+        let projection = perspective3(10., 0.1, 1., 90.);
+        shader.set_uniform("projection", Uniform::Matrix4(projection));
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::Viewport(0, 0, size as _, size as _);
+        }
+
+        for (face, view) in capture_views().into_iter().enumerate() {
+            shader.set_uniform("view", Uniform::Matrix4(view));
+            unsafe {
+                gl::FramebufferTexture2D(
+                    gl::FRAMEBUFFER,
+                    gl::COLOR_ATTACHMENT0,
+                    gl::TEXTURE_CUBE_MAP_POSITIVE_X + face as GLenum,
+                    target.id(),
+                    level as _,
+                );
+                gl::Clear(gl::COLOR_BUFFER_BIT);
+            }
+            shader.draw(&Transform::default(), &self.cube).unwrap();
+        }
+    }
+}
+
+/// Views from the origin towards each face, in `+X, -X, +Y, -Y, +Z, -Z` order.
+fn capture_views() -> [engine_math::Matrix4; 6] {
+    let eye = Vector3::zero();
+    [
+        (Vector3::new(1., 0., 0.), Vector3::new(0., -1., 0.)),
+        (Vector3::new(-1., 0., 0.), Vector3::new(0., -1., 0.)),
+        (Vector3::new(0., 1., 0.), Vector3::new(0., 0., 1.)),
+        (Vector3::new(0., -1., 0.), Vector3::new(0., 0., -1.)),
+        (Vector3::new(0., 0., 1.), Vector3::new(0., -1., 0.)),
+        (Vector3::new(0., 0., -1.), Vector3::new(0., -1., 0.)),
+    ]
+    .map(|(target, up)| lookat3(eye, target, up))
+}
+
+#[derive(Debug)]
+pub enum Error {
+    UnknownTexture(String),
+    /// The environment's texture target, which isn't `TEXTURE_CUBE_MAP`.
+    NotACubeMap(GLenum),
+    Framebuffer(framebuffer::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::UnknownTexture(name) => write!(f, "No texture is registered as `{name}`"),
+            Error::NotACubeMap(target) => write!(
+                f,
+                "Environments must be cube maps, got a texture with target 0x{target:x}"
+            ),
+            Error::Framebuffer(e) => write!(f, "Can't bake the BRDF lookup table: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
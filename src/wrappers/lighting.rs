@@ -0,0 +1,123 @@
+use crate::object::components::{Attenuation, Light, LightKind};
+
+use super::{
+    shader::ShaderSource,
+    types::{Vec3, Vec4},
+    uniform_buffer::UniformBlock,
+};
+
+const VERTEX_SHADER: &str = include_str!("../../shaders/lit_vertex.glsl");
+const FRAGMENT_SHADER: &str = include_str!("../../shaders/lit_fragment.glsl");
+
+/// Lights uploaded per frame, the rest are ignored. Must match `MAX_LIGHTS` in the lit shader.
+pub const MAX_LIGHTS: usize = 16;
+
+/// Built-in Blinn-Phong shader lighting the `diffuseMap` texture with the [`LightsBlock`].
+/// Configure it through [`Material::lit`](super::material::Material::lit).
+pub fn lit_shader() -> ShaderSource {
+    ShaderSource::from_strings(VERTEX_SHADER.to_string(), FRAGMENT_SHADER.to_string())
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct LightData {
+    /// `w` is the light kind: 0 directional, 1 point, 2 spot.
+    pub position: Vec4,
+    pub direction: Vec4,
+    /// Color premultiplied by intensity.
+    pub color: Vec4,
+    /// Constant, linear and quadratic factors.
+    pub attenuation: Vec4,
+    /// Cosines of the inner and outer cone angles.
+    pub cone: Vec4,
+    /// First shadow map layer, or -1 without shadows, layer count, bias and normal bias.
+    /// Filled in by [`assign_shadow_maps`](super::shadows::assign_shadow_maps).
+    pub shadow: Vec4,
+}
+
+impl LightData {
+    pub fn new(position: Vec3, light: &Light) -> Self {
+        let zero = Vec4::new(0., 0., 0., 0.);
+        let direction = |d: Vec3| Vec4::new(d.x, d.y, d.z, 0.);
+        let attenuation = |a: Attenuation| Vec4::new(a.constant, a.linear, a.quadratic, 0.);
+
+        let (kind, direction, attenuation, cone) = match light.kind {
+            LightKind::Directional { direction: d } => (0., direction(d), zero, zero),
+            LightKind::Point { attenuation: a } => (1., zero, attenuation(a), zero),
+            LightKind::Spot {
+                direction: d,
+                attenuation: a,
+                inner_angle,
+                outer_angle,
+            } => {
+                let (inner, outer) = (
+                    inner_angle.to_radians().cos(),
+                    outer_angle.to_radians().cos(),
+                );
+                // Equal cosines would divide by zero in the falloff.
+                let cone = Vec4::new(inner.max(outer + 1e-4), outer, 0., 0.);
+                (2., direction(d), attenuation(a), cone)
+            }
+        };
+
+        Self {
+            position: Vec4::new(position.x, position.y, position.z, kind),
+            direction,
+            color: Vec4::new(
+                light.color.x * light.intensity,
+                light.color.y * light.intensity,
+                light.color.z * light.intensity,
+                1.,
+            ),
+            attenuation,
+            cone,
+            shadow: Vec4::new(-1., 0., 0., 0.),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct LightsBlock {
+    pub ambient: Vec4,
+    pub count: i32,
+    _padding: [i32; 3],
+    pub lights: [LightData; MAX_LIGHTS],
+}
+
+impl LightsBlock {
+    /// Keeps the first [`MAX_LIGHTS`] of `lights`.
+    pub fn new<'a, I>(ambient: Vec3, lights: I) -> Self
+    where
+        I: IntoIterator<Item = (Vec3, &'a Light)>,
+    {
+        let zero = Vec4::new(0., 0., 0., 0.);
+        let mut block = Self {
+            ambient: Vec4::new(ambient.x, ambient.y, ambient.z, 1.),
+            count: 0,
+            _padding: [0; 3],
+            lights: [LightData {
+                position: zero,
+                direction: zero,
+                color: zero,
+                attenuation: zero,
+                cone: zero,
+                shadow: Vec4::new(-1., 0., 0., 0.),
+            }; MAX_LIGHTS],
+        };
+
+        lights
+            .into_iter()
+            .take(MAX_LIGHTS)
+            .for_each(|(position, light)| {
+                block.lights[block.count as usize] = LightData::new(position, light);
+                block.count += 1;
+            });
+
+        block
+    }
+}
+
+unsafe impl UniformBlock for LightsBlock {
+    const NAME: &'static str = "Lights";
+}
@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+
+use super::{
+    buffer_object::BufferObject,
+    ibl,
+    shader::Shader,
+    shadows,
+    types::{Uniform, Vec4},
+    uniform_buffer::{UniformBlock, MATERIAL_BLOCK_BINDING},
+};
+
+/// How a material's output is combined with what's already drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum BlendMode {
+    #[default]
+    Opaque,
+    /// Opaque, but fragments with alpha below `cutoff` are discarded.
+    /// Needs the shader to compare its alpha against the `alphaCutoff` uniform.
+    AlphaTest { cutoff: f32 },
+    /// Classic transparency, `src * a + dst * (1 - a)`.
+    AlphaBlend,
+    /// `src * a + dst`, for glows and particles.
+    Additive,
+    /// `src + dst * (1 - a)`, for colors already multiplied by their alpha.
+    Premultiplied,
+}
+
+impl BlendMode {
+    /// Blended materials are drawn after opaque ones, back to front, without writing depth.
+    pub fn is_transparent(&self) -> bool {
+        matches!(
+            self,
+            Self::AlphaBlend | Self::Additive | Self::Premultiplied
+        )
+    }
+
+    /// Sets the blending and depth write state for drawing with this mode.
+    pub fn apply(&self) {
+        unsafe {
+            let factors = match self {
+                Self::Opaque | Self::AlphaTest { .. } => None,
+                Self::AlphaBlend => Some((gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA)),
+                Self::Additive => Some((gl::SRC_ALPHA, gl::ONE)),
+                Self::Premultiplied => Some((gl::ONE, gl::ONE_MINUS_SRC_ALPHA)),
+            };
+
+            match factors {
+                Some((src, dst)) => {
+                    gl::Enable(gl::BLEND);
+                    gl::BlendFunc(src, dst);
+                    gl::DepthMask(gl::FALSE);
+                }
+                None => {
+                    gl::Disable(gl::BLEND);
+                    gl::DepthMask(gl::TRUE);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Material {
+    shader: String,
+    uniforms: HashMap<String, Uniform>,
+    textures: Vec<(String, String)>,
+    block: Option<(String, Vec<u8>)>,
+    blend_mode: BlendMode,
+}
+
+impl Material {
+    pub fn new<N: Into<String>>(shader: N) -> Self {
+        Self {
+            shader: shader.into(),
+            uniforms: HashMap::new(),
+            textures: vec![],
+            block: None,
+            blend_mode: BlendMode::Opaque,
+        }
+    }
+
+    /// Material for the engine's built-in [`LIT_SHADER`](crate::engine::LIT_SHADER),
+    /// plain white until a `diffuseMap` texture or `diffuseColor` is set.
+    ///
+    /// A `normalMap` is scaled by `normalScale`. Parallax occlusion mapping is off until
+    /// `heightScale` is raised above zero, around `0.05`, with a `heightMap` set.
+    /// Meshes should have [tangents](super::mesh::Mesh::with_computed_tangents) for both.
+    pub fn lit() -> Self {
+        Self::new(crate::engine::LIT_SHADER)
+            .set_texture("diffuseMap", crate::engine::WHITE_TEXTURE)
+            .set_texture("normalMap", crate::engine::FLAT_NORMAL_TEXTURE)
+            .set_texture("heightMap", crate::engine::WHITE_TEXTURE)
+            .set_texture("shadowMaps", shadows::SHADOW_MAPS)
+            .set_uniform("diffuseColor", Uniform::Vector4(Vec4::new(1., 1., 1., 1.)))
+            .set_uniform("specularStrength", Uniform::Float(0.5))
+            .set_uniform("shininess", Uniform::Float(32.))
+            .set_uniform("normalScale", Uniform::Float(1.))
+            .set_uniform("heightScale", Uniform::Float(0.))
+    }
+
+    pub fn set_uniform<N: Into<String>>(mut self, name: N, uniform: Uniform) -> Self {
+        self.uniforms.insert(name.into(), uniform);
+        self
+    }
+
+    /// Binds texture `texture` to the sampler uniform `sampler`.
+    pub fn set_texture<S, T>(mut self, sampler: S, texture: T) -> Self
+    where
+        S: Into<String>,
+        T: Into<String>,
+    {
+        let sampler = sampler.into();
+        let texture = texture.into();
+        match self.textures.iter_mut().find(|(s, _)| *s == sampler) {
+            Some(slot) => slot.1 = texture,
+            None => self.textures.push((sampler, texture)),
+        }
+        self
+    }
+
+    /// Sets the uniform block uploaded to [`MATERIAL_BLOCK_BINDING`] whenever this material is drawn.
+    pub fn set_block<T: UniformBlock>(mut self, block: T) -> Self {
+        self.block = Some((T::NAME.to_string(), block.as_bytes().to_vec()));
+        self
+    }
+
+    pub fn set_blend_mode(mut self, blend_mode: BlendMode) -> Self {
+        self.blend_mode = blend_mode;
+        self
+    }
+
+    pub fn shader(&self) -> &str {
+        &self.shader
+    }
+
+    pub fn uniforms(&self) -> &HashMap<String, Uniform> {
+        &self.uniforms
+    }
+
+    pub fn textures(&self) -> &[(String, String)] {
+        &self.textures
+    }
+
+    pub fn blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+
+    /// Uploads uniform values and the material block and sets the blend state,
+    /// `shader` must already be the current program.
+    /// Textures are bound by the renderer together with the object's own textures.
+    pub fn apply(&self, shader: &Shader, block_buffer: &BufferObject) {
+        self.blend_mode.apply();
+        // Always set, programs keep uniforms between the materials sharing them.
+        let cutoff = match self.blend_mode {
+            BlendMode::AlphaTest { cutoff } => cutoff,
+            _ => 0.,
+        };
+        shader.set_uniform_bound("alphaCutoff", Uniform::Float(cutoff));
+
+        if let Some((name, data)) = &self.block {
+            shader.bind_uniform_block(name, MATERIAL_BLOCK_BINDING);
+            block_buffer.bind();
+            block_buffer.upload_data(data);
+            block_buffer.bind_base(MATERIAL_BLOCK_BINDING);
+        }
+
+        self.uniforms
+            .iter()
+            .for_each(|(name, uniform)| shader.set_uniform_bound(name, *uniform));
+    }
+}
+
+/// Metallic-roughness material for the engine's built-in [`PBR_SHADER`](crate::engine::PBR_SHADER),
+/// following the glTF conventions. Unset maps default to white, or a flat normal,
+/// so the factors alone describe the surface.
+///
+/// Albedo and emissive maps hold colors and should be loaded as sRGB, the others
+/// hold data and need [`ColorSpace::Linear`](super::types::ColorSpace::Linear).
+#[derive(Debug, Clone)]
+pub struct PbrMaterial {
+    albedo_map: String,
+    metallic_roughness_map: String,
+    ao_map: String,
+    emissive_map: String,
+    normal_map: String,
+    albedo: Vec4,
+    metallic: f32,
+    roughness: f32,
+    ao_strength: f32,
+    normal_scale: f32,
+    emissive: Vec4,
+    blend_mode: BlendMode,
+}
+
+impl Default for PbrMaterial {
+    fn default() -> Self {
+        Self {
+            albedo_map: crate::engine::WHITE_TEXTURE.to_string(),
+            metallic_roughness_map: crate::engine::WHITE_TEXTURE.to_string(),
+            ao_map: crate::engine::WHITE_TEXTURE.to_string(),
+            emissive_map: crate::engine::WHITE_TEXTURE.to_string(),
+            normal_map: crate::engine::FLAT_NORMAL_TEXTURE.to_string(),
+            albedo: Vec4::new(1., 1., 1., 1.),
+            metallic: 0.,
+            roughness: 0.5,
+            ao_strength: 1.,
+            normal_scale: 1.,
+            emissive: Vec4::new(0., 0., 0., 0.),
+            blend_mode: BlendMode::Opaque,
+        }
+    }
+}
+
+impl PbrMaterial {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_albedo_map<N: Into<String>>(mut self, texture: N) -> Self {
+        self.albedo_map = texture.into();
+        self
+    }
+
+    /// Roughness is read from the green channel, metallic from the blue one.
+    pub fn set_metallic_roughness_map<N: Into<String>>(mut self, texture: N) -> Self {
+        self.metallic_roughness_map = texture.into();
+        self
+    }
+
+    /// Ambient occlusion is read from the red channel.
+    pub fn set_ao_map<N: Into<String>>(mut self, texture: N) -> Self {
+        self.ao_map = texture.into();
+        self
+    }
+
+    pub fn set_emissive_map<N: Into<String>>(mut self, texture: N) -> Self {
+        self.emissive_map = texture.into();
+        self
+    }
+
+    /// Tangent-space normal map, OpenGL convention (green up).
+    pub fn set_normal_map<N: Into<String>>(mut self, texture: N) -> Self {
+        self.normal_map = texture.into();
+        self
+    }
+
+    /// Linear color multiplied with the albedo map, alpha included.
+    pub fn set_albedo(mut self, albedo: Vec4) -> Self {
+        self.albedo = albedo;
+        self
+    }
+
+    pub fn set_metallic(mut self, metallic: f32) -> Self {
+        self.metallic = metallic;
+        self
+    }
+
+    pub fn set_roughness(mut self, roughness: f32) -> Self {
+        self.roughness = roughness;
+        self
+    }
+
+    /// `0.0` ignores the AO map, `1.0` applies it fully.
+    pub fn set_ao_strength(mut self, strength: f32) -> Self {
+        self.ao_strength = strength;
+        self
+    }
+
+    pub fn set_normal_scale(mut self, scale: f32) -> Self {
+        self.normal_scale = scale;
+        self
+    }
+
+    /// Linear color multiplied with the emissive map, may exceed `1.0` for HDR glow.
+    pub fn set_emissive(mut self, emissive: Vec4) -> Self {
+        self.emissive = emissive;
+        self
+    }
+
+    /// Alpha comes from the albedo map and factor.
+    pub fn set_blend_mode(mut self, blend_mode: BlendMode) -> Self {
+        self.blend_mode = blend_mode;
+        self
+    }
+}
+
+impl From<PbrMaterial> for Material {
+    fn from(pbr: PbrMaterial) -> Self {
+        Material::new(crate::engine::PBR_SHADER)
+            .set_texture("albedoMap", pbr.albedo_map)
+            .set_texture("metallicRoughnessMap", pbr.metallic_roughness_map)
+            .set_texture("aoMap", pbr.ao_map)
+            .set_texture("emissiveMap", pbr.emissive_map)
+            .set_texture("normalMap", pbr.normal_map)
+            .set_texture("irradianceMap", ibl::IRRADIANCE_MAP)
+            .set_texture("prefilteredMap", ibl::PREFILTERED_MAP)
+            .set_texture("brdfLUT", ibl::BRDF_LUT)
+            .set_texture("shadowMaps", shadows::SHADOW_MAPS)
+            .set_uniform("albedoFactor", Uniform::Vector4(pbr.albedo))
+            .set_uniform("metallicFactor", Uniform::Float(pbr.metallic))
+            .set_uniform("roughnessFactor", Uniform::Float(pbr.roughness))
+            .set_uniform("aoStrength", Uniform::Float(pbr.ao_strength))
+            .set_uniform("normalScale", Uniform::Float(pbr.normal_scale))
+            .set_uniform("emissiveFactor", Uniform::Vector4(pbr.emissive))
+            .set_blend_mode(pbr.blend_mode)
+    }
+}
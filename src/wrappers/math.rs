@@ -0,0 +1,34 @@
+use super::types::Vec3;
+
+pub(crate) fn add(a: Vec3, b: Vec3) -> Vec3 {
+    Vec3::new(a.x + b.x, a.y + b.y, a.z + b.z)
+}
+
+pub(crate) fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    Vec3::new(a.x - b.x, a.y - b.y, a.z - b.z)
+}
+
+pub(crate) fn mul(a: Vec3, s: f32) -> Vec3 {
+    Vec3::new(a.x * s, a.y * s, a.z * s)
+}
+
+pub(crate) fn dot(a: Vec3, b: Vec3) -> f32 {
+    a.x * b.x + a.y * b.y + a.z * b.z
+}
+
+pub(crate) fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    Vec3::new(
+        a.y * b.z - a.z * b.y,
+        a.z * b.x - a.x * b.z,
+        a.x * b.y - a.y * b.x,
+    )
+}
+
+pub(crate) fn length(a: Vec3) -> f32 {
+    dot(a, a).sqrt()
+}
+
+/// Zero vectors stay zero instead of turning into NaNs.
+pub(crate) fn normalize(a: Vec3) -> Vec3 {
+    mul(a, 1. / length(a).max(f32::EPSILON))
+}
@@ -1,26 +1,81 @@
 use std::mem::size_of;
 
+use engine_math::Matrix;
+use gl::types::GLuint;
+
 use super::{
     attribute_pointer::{AttributePointers, Attributes},
     buffer_object::BufferObject,
+    culling::Bounds,
+    math::{add, cross, dot, length, mul, normalize, sub},
     shader::Shader,
-    types::{Index, Vec2, Vec3},
+    types::{BufferObjectType, DrawType, Index, Mat4, PrimitiveType, Vec2, Vec3, Vec4},
     vertex_array::VertexArray,
 };
 
 pub trait Draw {
-    fn draw(&self);
+    fn draw(&self) {
+        self.draw_primitive(PrimitiveType::Triangles);
+    }
+
+    fn draw_primitive(&self, primitive: PrimitiveType);
 }
 
+/// First of the four locations taking the [`InstanceData`] model matrix.
+pub const INSTANCE_MODEL_LOCATION: u32 = 4;
+/// Vertex shaders declaring this `mat4` input at [`INSTANCE_MODEL_LOCATION`]
+/// are drawn instanced by the engine instead of reading a `model` uniform.
+pub const INSTANCE_MODEL_ATTRIBUTE: &str = "instanceModel";
+
 #[derive(Debug, Clone)]
 pub struct BoundStaticMesh {
     vao: VertexArray,
+    attributes: Vec<AttributePointers>,
+    instances: BufferObject,
+    bounds: Bounds,
 }
 
-impl Draw for BoundStaticMesh {
-    fn draw(&self) {
+impl BoundStaticMesh {
+    /// Vertex layout the mesh was uploaded with.
+    pub fn attributes(&self) -> &[AttributePointers] {
+        &self.attributes
+    }
+
+    /// Model space box around the vertices, computed at upload.
+    pub fn bounds(&self) -> &Bounds {
+        &self.bounds
+    }
+
+    /// Vertex array the mesh is bound through.
+    pub fn id(&self) -> GLuint {
+        self.vao.id()
+    }
+
+    pub fn bind(&self) {
         self.vao.bind();
-        self.vao.draw();
+    }
+
+    /// [`Draw::draw_primitive`] for a mesh that is already bound.
+    pub fn draw_bound(&self, primitive: PrimitiveType) {
+        self.vao.draw_primitive(primitive);
+    }
+
+    /// Draws one copy of the already bound mesh per element of `instances`, whose
+    /// layout is read from its [`Attributes`] implementation at every call.
+    pub fn draw_instanced<T: Attributes>(&self, instances: &[T], primitive: PrimitiveType) {
+        self.instances.bind();
+        self.instances.upload_data(instances);
+        T::get_attributes()
+            .into_iter()
+            .for_each(|attribute| self.vao.vertex_attrib_pointer(attribute));
+        self.vao.draw_instanced(primitive, instances.len());
+    }
+}
+
+impl Draw for BoundStaticMesh {
+    fn draw_primitive(&self, primitive: PrimitiveType) {
+        self.bind();
+        self.draw_bound(primitive);
     }
 }
 
@@ -35,7 +90,93 @@ impl Mesh {
         Self { vertices, indices }
     }
 
+    /// Replaces vertex normals with the area-weighted average of the triangles sharing each vertex.
+    /// Vertices duplicated per face end up with flat normals.
+    pub fn with_computed_normals(mut self) -> Self {
+        let mut normals = vec![Vec3::new(0., 0., 0.); self.vertices.len()];
+        self.indices.chunks_exact(3).for_each(|triangle| {
+            let [a, b, c] = [0, 1, 2].map(|i| self.vertices[triangle[i] as usize].pos);
+            let face = cross(sub(b, a), sub(c, a));
+            triangle.iter().for_each(|&i| {
+                normals[i as usize] = add(normals[i as usize], face);
+            });
+        });
+
+        self.vertices
+            .iter_mut()
+            .zip(normals)
+            .for_each(|(vertex, normal)| vertex.normal = normalize(normal));
+        self
+    }
+
+    /// Generates tangents from positions, normals, UVs and indices: each triangle's UV direction
+    /// is projected onto the vertex normal's plane and weighted by the corner angle, and `w` stores
+    /// the handedness. Vertices are neither welded nor split as MikkTSpace does, so normal maps
+    /// baked with it may differ slightly. Normals must already be set, and vertices shared across
+    /// mirrored UVs should be split beforehand.
+    pub fn with_computed_tangents(mut self) -> Self {
+        let zero = Vec3::new(0., 0., 0.);
+        let mut tangents = vec![zero; self.vertices.len()];
+        let mut bitangents = vec![zero; self.vertices.len()];
+
+        self.indices.chunks_exact(3).for_each(|triangle| {
+            let corners = [0, 1, 2].map(|i| self.vertices[triangle[i] as usize]);
+            let [a, b, c] = corners.map(|v| v.pos);
+            let [ta, tb, tc] = corners.map(|v| v.tex);
+
+            let (e1, e2) = (sub(b, a), sub(c, a));
+            let (du1, dv1, du2, dv2) = (tb.x - ta.x, tb.y - ta.y, tc.x - ta.x, tc.y - ta.y);
+            let det = du1 * dv2 - du2 * dv1;
+            if det.abs() <= f32::EPSILON {
+                return;
+            }
+            let sdir = mul(sub(mul(e1, dv2), mul(e2, dv1)), 1. / det);
+            let tdir = mul(sub(mul(e2, du1), mul(e1, du2)), 1. / det);
+
+            let positions = [a, b, c];
+            (0..3).for_each(|corner| {
+                let normal = corners[corner].normal;
+                let project = |v: Vec3| normalize(sub(v, mul(normal, dot(normal, v))));
+
+                let p = positions[corner];
+                let to_next = normalize(sub(positions[(corner + 1) % 3], p));
+                let to_prev = normalize(sub(positions[(corner + 2) % 3], p));
+                let angle = dot(to_next, to_prev).clamp(-1., 1.).acos();
+
+                let index = triangle[corner] as usize;
+                tangents[index] = add(tangents[index], mul(project(sdir), angle));
+                bitangents[index] = add(bitangents[index], mul(project(tdir), angle));
+            });
+        });
+
+        self.vertices
+            .iter_mut()
+            .zip(tangents.into_iter().zip(bitangents))
+            .for_each(|(vertex, (tangent, bitangent))| {
+                let normal = vertex.normal;
+                let in_plane = |v: Vec3| sub(v, mul(normal, dot(normal, v)));
+                let tangent = match in_plane(tangent) {
+                    tangent if length(tangent) > f32::EPSILON => normalize(tangent),
+                    // No usable UVs, any direction in the normal's plane will do.
+                    _ if normal.x.abs() < 0.9 => normalize(in_plane(Vec3::new(1., 0., 0.))),
+                    _ => normalize(in_plane(Vec3::new(0., 1., 0.))),
+                };
+                let handedness = if dot(cross(normal, tangent), bitangent) < 0. {
+                    -1.
+                } else {
+                    1.
+                };
+                vertex.tangent = Vec4::new(tangent.x, tangent.y, tangent.z, handedness);
+            });
+        self
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        Bounds::from_points(self.vertices.iter().map(|v| v.pos))
+    }
+
     pub fn create_static(self, _: &Shader) -> BoundStaticMesh {
+        let bounds = self.bounds();
         let Mesh { vertices, indices } = self;
 
         let vbo = BufferObject::vertex_buffer_object();
@@ -44,11 +185,19 @@ impl Mesh {
 
         vao.bind();
         vao.upload_data(&vertices, &indices);
-        Vertex::get_attributes()
+        let attributes = Vertex::get_attributes();
+        attributes
             .iter()
             .for_each(|v| vao.vertex_attrib_pointer(*v));
 
-        BoundStaticMesh { vao }
+        let instances = BufferObject::new(BufferObjectType::ArrayBuffer, DrawType::StreamDraw);
+
+        BoundStaticMesh {
+            vao,
+            attributes,
+            instances,
+            bounds,
+        }
     }
 }
 
@@ -57,6 +206,10 @@ impl Mesh {
 pub struct Vertex {
     pub pos: Vec3,
     pub tex: Vec2,
+    pub normal: Vec3,
+    /// Tangent in `xyz` and handedness in `w`, the bitangent being `w * cross(normal, tangent)`.
+    /// Left at zero, shaders derive a tangent frame from screen-space derivatives instead.
+    pub tangent: Vec4,
 }
 
 impl Attributes for Vertex {
@@ -75,6 +228,134 @@ impl Attributes for Vertex {
                 ptr: size_of::<Vec3>() as _,
                 ..AttributePointers::empty()
             },
+            AttributePointers {
+                location: 2,
+                size: 3,
+                stride: size,
+                ptr: (size_of::<Vec3>() + size_of::<Vec2>()) as _,
+                ..AttributePointers::empty()
+            },
+            AttributePointers {
+                location: 3,
+                size: 4,
+                stride: size,
+                ptr: (2 * size_of::<Vec3>() + size_of::<Vec2>()) as _,
+                ..AttributePointers::empty()
+            },
         ]
     }
 }
+
+/// Per-instance model matrix, see [`INSTANCE_MODEL_ATTRIBUTE`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct InstanceData {
+    /// Column-major.
+    model: Mat4,
+}
+
+impl InstanceData {
+    pub fn new(model: Mat4) -> Self {
+        Self {
+            model: model.transpose(),
+        }
+    }
+}
+
+impl Attributes for InstanceData {
+    fn get_attributes() -> Vec<AttributePointers> {
+        let size = size_of::<InstanceData>();
+        // A `mat4` input takes one location per column.
+        (0..4)
+            .map(|column| AttributePointers {
+                location: INSTANCE_MODEL_LOCATION + column,
+                size: 4,
+                stride: size,
+                ptr: (column as usize * size_of::<Vec4>()) as _,
+                divisor: 1,
+                ..AttributePointers::empty()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Unit quad in the XY plane facing +Z, with the given UV per corner.
+    fn quad(uvs: [(f32, f32); 4]) -> Mesh {
+        let positions = [(0., 0.), (1., 0.), (1., 1.), (0., 1.)];
+        let vertices = positions
+            .into_iter()
+            .zip(uvs)
+            .map(|((x, y), (u, v))| Vertex {
+                pos: Vec3::new(x, y, 0.),
+                tex: Vec2::new(u, v),
+                normal: Vec3::new(0., 0., 1.),
+                tangent: Vec4::new(0., 0., 0., 0.),
+            })
+            .collect();
+        Mesh::new(vertices, vec![0, 1, 2, 0, 2, 3])
+    }
+
+    fn assert_tangents(mesh: &Mesh, expected: [f32; 4]) {
+        for vertex in &mesh.vertices {
+            let t = vertex.tangent;
+            let actual = [t.x, t.y, t.z, t.w];
+            assert!(
+                actual
+                    .iter()
+                    .zip(expected)
+                    .all(|(a, e)| (a - e).abs() < 1e-5),
+                "expected {expected:?}, got {actual:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn instance_data_takes_a_location_per_column() {
+        let attributes = InstanceData::get_attributes();
+
+        assert_eq!(attributes.len(), 4);
+        for (column, attribute) in attributes.iter().enumerate() {
+            assert_eq!(attribute.location, INSTANCE_MODEL_LOCATION + column as u32);
+            assert_eq!(attribute.size, 4);
+            assert_eq!(attribute.stride, size_of::<InstanceData>());
+            assert_eq!(attribute.ptr as usize, column * size_of::<Vec4>());
+            assert_eq!(attribute.divisor, 1);
+        }
+    }
+
+    #[test]
+    fn tangents_follow_u() {
+        let mesh = quad([(0., 0.), (1., 0.), (1., 1.), (0., 1.)]).with_computed_tangents();
+        assert_tangents(&mesh, [1., 0., 0., 1.]);
+    }
+
+    #[test]
+    fn mirrored_uvs_flip_handedness() {
+        let mesh = quad([(1., 0.), (0., 0.), (0., 1.), (1., 1.)]).with_computed_tangents();
+        assert_tangents(&mesh, [-1., 0., 0., -1.]);
+    }
+
+    #[test]
+    fn degenerate_uvs_fall_back_to_the_normal_plane() {
+        let mesh = quad([(0., 0.); 4]).with_computed_tangents();
+        assert_tangents(&mesh, [1., 0., 0., 1.]);
+    }
+
+    #[test]
+    fn computed_normals_face_the_winding() {
+        let mut mesh = quad([(0., 0.); 4]);
+        mesh.vertices
+            .iter_mut()
+            .for_each(|v| v.normal = Vec3::new(0., 0., 0.));
+        let mesh = mesh.with_computed_normals();
+
+        for vertex in &mesh.vertices {
+            let n = vertex.normal;
+            assert_eq!([n.x, n.y, n.z], [0., 0., 1.]);
+        }
+    }
+}
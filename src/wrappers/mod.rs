@@ -1,10 +1,26 @@
+pub mod atlas;
 pub mod attribute_pointer;
 pub mod buffer_object;
+pub mod compressed;
+pub mod culling;
+pub mod framebuffer;
 pub mod gl;
+pub mod ibl;
+pub mod lighting;
+pub mod material;
+pub(crate) mod math;
 pub mod mesh;
+pub mod post_process;
+pub mod procedural;
+pub mod program_cache;
+pub mod reflection;
+pub mod render_queue;
 pub mod shader;
+pub mod shadows;
+pub mod skybox;
 pub mod textures;
 pub mod types;
+pub mod uniform_buffer;
 pub mod vertex_array;
 pub mod window;
 
@@ -0,0 +1,402 @@
+use std::collections::HashMap;
+
+use gl::types::GLuint;
+
+use super::{
+    framebuffer::{self, AttachmentStorage, BuilderFramebuffer, Framebuffer},
+    gl::GL,
+    shader::{Shader, ShaderSource},
+    textures::{Texture, Texture2D},
+    types::{PixelFormat, Uniform, Vec4},
+};
+
+pub const FULLSCREEN_VERTEX_SHADER: &str =
+    include_str!("../../shaders/post/fullscreen_vertex.glsl");
+const COPY: &str = include_str!("../../shaders/post/copy.glsl");
+const TONEMAP: &str = include_str!("../../shaders/post/tonemap.glsl");
+const BLOOM_THRESHOLD: &str = include_str!("../../shaders/post/bloom_threshold.glsl");
+const BLUR: &str = include_str!("../../shaders/post/blur.glsl");
+const BLOOM_COMPOSITE: &str = include_str!("../../shaders/post/bloom_composite.glsl");
+const FXAA: &str = include_str!("../../shaders/post/fxaa.glsl");
+const VIGNETTE: &str = include_str!("../../shaders/post/vignette.glsl");
+const COLOR_GRADING: &str = include_str!("../../shaders/post/color_grading.glsl");
+
+/// Name of the color attachment of every post-processing target.
+const COLOR: &str = "color";
+
+/// Source for a custom effect: `fragment_shader` gets `in vec2 TexCoord`
+/// and the previous pass' output as `uniform sampler2D screen`.
+pub fn fullscreen_shader(fragment_shader: String) -> ShaderSource {
+    ShaderSource::from_strings(FULLSCREEN_VERTEX_SHADER.to_string(), fragment_shader)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tonemapper {
+    Reinhard,
+    Aces,
+}
+
+/// A full-screen pass. Effects before `Tonemap` see linear HDR colors,
+/// effects after it see colors in `[0, 1]`.
+#[derive(Debug, Clone)]
+pub enum PostEffect {
+    Tonemap {
+        exposure: f32,
+        operator: Tonemapper,
+    },
+    /// Adds a blurred copy of everything brighter than `threshold`.
+    Bloom {
+        threshold: f32,
+        intensity: f32,
+        blur_passes: u32,
+    },
+    Fxaa,
+    Vignette {
+        intensity: f32,
+        smoothness: f32,
+    },
+    /// `lut` names a registered 3D texture mapping input colors to graded ones.
+    ColorGrading {
+        lut: String,
+        intensity: f32,
+    },
+    /// `shader` names a registered shader, usually built with [`fullscreen_shader`].
+    Custom {
+        shader: String,
+        uniforms: HashMap<String, Uniform>,
+    },
+}
+
+impl PostEffect {
+    pub fn tonemap() -> Self {
+        Self::Tonemap {
+            exposure: 1.,
+            operator: Tonemapper::Aces,
+        }
+    }
+
+    pub fn bloom() -> Self {
+        Self::Bloom {
+            threshold: 1.,
+            intensity: 0.5,
+            blur_passes: 4,
+        }
+    }
+
+    pub fn vignette() -> Self {
+        Self::Vignette {
+            intensity: 0.4,
+            smoothness: 0.6,
+        }
+    }
+
+    pub fn color_grading<N: Into<String>>(lut: N) -> Self {
+        Self::ColorGrading {
+            lut: lut.into(),
+            intensity: 1.,
+        }
+    }
+
+    pub fn custom<N: Into<String>>(shader: N) -> Self {
+        Self::Custom {
+            shader: shader.into(),
+            uniforms: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PostPass {
+    pub name: String,
+    pub enabled: bool,
+    pub effect: PostEffect,
+}
+
+#[derive(Debug)]
+struct BuiltinShaders {
+    copy: Shader,
+    tonemap: Shader,
+    bloom_threshold: Shader,
+    blur: Shader,
+    bloom_composite: Shader,
+    fxaa: Shader,
+    vignette: Shader,
+    color_grading: Shader,
+}
+
+/// Ordered chain of full-screen effects run between the scene and the window.
+///
+/// The scene is drawn into an HDR target, each enabled pass reads the previous
+/// output and the last one writes to the default framebuffer.
+#[derive(Debug)]
+pub struct PostProcess {
+    passes: Vec<PostPass>,
+    shaders: BuiltinShaders,
+    scene: Framebuffer,
+    targets: [Framebuffer; 2],
+    bloom: [Framebuffer; 2],
+    vao: GLuint,
+}
+
+impl PostProcess {
+    /// Fails if the driver can't render to the HDR targets at that size.
+    pub fn new(gl: &GL, (width, height): (u32, u32)) -> Result<Self, framebuffer::Error> {
+        let compile = |fragment: &str| fullscreen_shader(fragment.to_string()).compile(gl);
+        let shaders = BuiltinShaders {
+            copy: compile(COPY),
+            tonemap: compile(TONEMAP),
+            bloom_threshold: compile(BLOOM_THRESHOLD),
+            blur: compile(BLUR),
+            bloom_composite: compile(BLOOM_COMPOSITE),
+            fxaa: compile(FXAA),
+            vignette: compile(VIGNETTE),
+            color_grading: compile(COLOR_GRADING),
+        };
+
+        let scene = hdr_target((width, height))
+            .set_depth_stencil(
+                PixelFormat::DEPTH24_STENCIL8,
+                AttachmentStorage::Renderbuffer,
+            )
+            .build()?;
+        let targets = [
+            hdr_target((width, height)).build()?,
+            hdr_target((width, height)).build()?,
+        ];
+        let bloom = [
+            hdr_target(half(width, height)).build()?,
+            hdr_target(half(width, height)).build()?,
+        ];
+
+        // Core profiles can't draw without a vertex array, even with no attributes.
+        let mut vao = 0;
+        unsafe { gl::GenVertexArrays(1, &mut vao) };
+
+        Ok(Self {
+            passes: vec![],
+            shaders,
+            scene,
+            targets,
+            bloom,
+            vao,
+        })
+    }
+
+    pub fn passes(&self) -> &[PostPass] {
+        &self.passes
+    }
+
+    /// Whether any pass is enabled, otherwise the scene is drawn straight to the window.
+    pub fn is_active(&self) -> bool {
+        self.passes.iter().any(|pass| pass.enabled)
+    }
+
+    /// HDR target the scene is drawn into while the chain is active.
+    pub fn scene_target(&self) -> &Framebuffer {
+        &self.scene
+    }
+
+    /// Appends an enabled pass, replacing the one already called `name`.
+    pub fn add<N: Into<String>>(&mut self, name: N, effect: PostEffect) {
+        let name = name.into();
+        match self.pass_mut(&name) {
+            Some(pass) => pass.effect = effect,
+            None => self.passes.push(PostPass {
+                name,
+                enabled: true,
+                effect,
+            }),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<PostPass> {
+        let index = self.passes.iter().position(|pass| pass.name == name)?;
+        Some(self.passes.remove(index))
+    }
+
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) {
+        if let Some(pass) = self.pass_mut(name) {
+            pass.enabled = enabled;
+        }
+    }
+
+    pub fn set_effect(&mut self, name: &str, effect: PostEffect) {
+        if let Some(pass) = self.pass_mut(name) {
+            pass.effect = effect;
+        }
+    }
+
+    /// Moves the pass to `index` in the chain, clamped to the last position.
+    pub fn move_to(&mut self, name: &str, index: usize) {
+        if let Some(pass) = self.remove(name) {
+            let index = index.min(self.passes.len());
+            self.passes.insert(index, pass);
+        }
+    }
+
+    pub fn pass_mut(&mut self, name: &str) -> Option<&mut PostPass> {
+        self.passes.iter_mut().find(|pass| pass.name == name)
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<(), framebuffer::Error> {
+        let (half_width, half_height) = half(width, height);
+        self.scene.resize(width, height)?;
+        self.targets
+            .iter_mut()
+            .try_for_each(|target| target.resize(width, height))?;
+        self.bloom
+            .iter_mut()
+            .try_for_each(|target| target.resize(half_width, half_height))
+    }
+
+    /// Runs every enabled pass over `input`, ending in the default framebuffer.
+    /// Passes referring to missing shaders or textures copy their input through.
+    pub fn run(
+        &self,
+        input: &Texture2D,
+        shaders: &HashMap<String, Shader>,
+        textures: &HashMap<String, Box<dyn Texture>>,
+        screen: (i32, i32),
+    ) {
+        let passes: Vec<_> = self.passes.iter().filter(|pass| pass.enabled).collect();
+
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+            gl::BindVertexArray(self.vao);
+        }
+
+        let mut source = input;
+        for (i, pass) in passes.iter().enumerate() {
+            let target = (i + 1 < passes.len()).then(|| &self.targets[i % 2]);
+            self.apply(&pass.effect, source, target, shaders, textures, screen);
+            if let Some(target) = target {
+                source = target.texture(COLOR).unwrap();
+            }
+        }
+
+        unsafe { gl::Enable(gl::DEPTH_TEST) };
+    }
+
+    fn apply(
+        &self,
+        effect: &PostEffect,
+        source: &Texture2D,
+        target: Option<&Framebuffer>,
+        shaders: &HashMap<String, Shader>,
+        textures: &HashMap<String, Box<dyn Texture>>,
+        screen: (i32, i32),
+    ) {
+        let builtin = &self.shaders;
+        let (shader, extra): (&Shader, Option<(&str, &dyn Texture)>) = match effect {
+            PostEffect::Tonemap { exposure, operator } => {
+                builtin
+                    .tonemap
+                    .set_uniform("exposure", Uniform::Float(*exposure));
+                builtin
+                    .tonemap
+                    .set_uniform("operator", Uniform::Int(*operator as i32));
+                (&builtin.tonemap, None)
+            }
+            PostEffect::Bloom {
+                threshold,
+                intensity,
+                blur_passes,
+            } => {
+                let bloom = self.blur_bright_areas(source, *threshold, *blur_passes);
+                builtin
+                    .bloom_composite
+                    .set_uniform("intensity", Uniform::Float(*intensity));
+                (&builtin.bloom_composite, Some(("bloom", bloom)))
+            }
+            PostEffect::Fxaa => (&builtin.fxaa, None),
+            PostEffect::Vignette {
+                intensity,
+                smoothness,
+            } => {
+                builtin
+                    .vignette
+                    .set_uniform("intensity", Uniform::Float(*intensity));
+                builtin
+                    .vignette
+                    .set_uniform("smoothness", Uniform::Float(*smoothness));
+                (&builtin.vignette, None)
+            }
+            PostEffect::ColorGrading { lut, intensity } => match textures.get(lut) {
+                Some(lut) => {
+                    builtin
+                        .color_grading
+                        .set_uniform("intensity", Uniform::Float(*intensity));
+                    (&builtin.color_grading, Some(("lut", lut.as_ref())))
+                }
+                None => (&builtin.copy, None),
+            },
+            PostEffect::Custom { shader, uniforms } => match shaders.get(shader) {
+                Some(shader) => {
+                    uniforms
+                        .iter()
+                        .for_each(|(name, uniform)| shader.set_uniform(name, *uniform));
+                    (shader, None)
+                }
+                None => (&builtin.copy, None),
+            },
+        };
+
+        match target {
+            Some(target) => target.bind(),
+            None => unsafe {
+                Framebuffer::unbind();
+                gl::Viewport(0, 0, screen.0, screen.1);
+            },
+        }
+
+        let screen_texture: (&str, &dyn Texture) = ("screen", source);
+        shader.bind_textures(std::iter::once(screen_texture).chain(extra));
+        self.draw(shader);
+    }
+
+    /// Bright-pass into the half resolution targets, then separable blur back and forth.
+    fn blur_bright_areas(&self, source: &Texture2D, threshold: f32, passes: u32) -> &Texture2D {
+        let [a, b] = &self.bloom;
+        let builtin = &self.shaders;
+
+        a.bind();
+        builtin
+            .bloom_threshold
+            .set_uniform("threshold", Uniform::Float(threshold));
+        builtin
+            .bloom_threshold
+            .bind_textures([("screen", source as &dyn Texture)]);
+        self.draw(&builtin.bloom_threshold);
+
+        for _ in 0..passes {
+            for (from, to, direction) in [
+                (a, b, Vec4::new(1., 0., 0., 0.)),
+                (b, a, Vec4::new(0., 1., 0., 0.)),
+            ] {
+                to.bind();
+                builtin
+                    .blur
+                    .set_uniform("direction", Uniform::Vector4(direction));
+                builtin
+                    .blur
+                    .bind_textures([("screen", from.texture(COLOR).unwrap() as &dyn Texture)]);
+                self.draw(&builtin.blur);
+            }
+        }
+
+        a.texture(COLOR).unwrap()
+    }
+
+    fn draw(&self, shader: &Shader) {
+        shader.use_program();
+        unsafe { gl::DrawArrays(gl::TRIANGLES, 0, 3) };
+    }
+}
+
+fn hdr_target((width, height): (u32, u32)) -> BuilderFramebuffer {
+    Framebuffer::builder(width, height).add_color(COLOR, PixelFormat::RGBA16F)
+}
+
+fn half(width: u32, height: u32) -> (u32, u32) {
+    ((width / 2).max(1), (height / 2).max(1))
+}
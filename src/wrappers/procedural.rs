@@ -0,0 +1,186 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use super::{
+    textures::{BuilderTexture2D, Texture2D, TextureParameters},
+    types::Vec4,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gradient {
+    /// From left to right.
+    Horizontal,
+    /// From the first row to the last.
+    Vertical,
+    /// From the center to the corners.
+    Radial,
+}
+
+impl Texture2D {
+    /// Generates the texture pixel by pixel, `f` gets the pixel coordinates.
+    /// Colors are `[0, 1]` components stored as given, so they're sRGB values
+    /// unless the texture is switched to [`ColorSpace::Linear`](super::types::ColorSpace::Linear).
+    pub fn from_fn<F>((width, height): (u32, u32), name: &str, mut f: F) -> BuilderTexture2D
+    where
+        F: FnMut(u32, u32) -> Vec4,
+    {
+        let img = RgbaImage::from_fn(width, height, |x, y| to_rgba(f(x, y)));
+        Self::from_image(DynamicImage::ImageRgba8(img), name)
+    }
+
+    pub fn solid_color(color: Vec4, name: &str) -> BuilderTexture2D {
+        Self::from_fn((1, 1), name, |_, _| color)
+    }
+
+    /// `cells` squares along each side, alternating between `a` and `b`.
+    pub fn checkerboard(size: u32, cells: u32, a: Vec4, b: Vec4, name: &str) -> BuilderTexture2D {
+        let cell = (size / cells.max(1)).max(1);
+        Self::from_fn((size, size), name, |x, y| {
+            if (x / cell + y / cell) & 1 == 0 {
+                a
+            } else {
+                b
+            }
+        })
+        .set_parameters(TextureParameters::nearest())
+    }
+
+    /// Grayscale value noise with `frequency` lattice cells along each side.
+    /// The same `seed` always produces the same texture.
+    pub fn noise(size: u32, frequency: u32, seed: u32, name: &str) -> BuilderTexture2D {
+        let frequency = frequency.max(1) as f32;
+        Self::from_fn((size, size), name, |x, y| {
+            let v = value_noise(
+                x as f32 / size as f32 * frequency,
+                y as f32 / size as f32 * frequency,
+                seed,
+            );
+            Vec4::new(v, v, v, 1.)
+        })
+    }
+
+    pub fn gradient(
+        (width, height): (u32, u32),
+        from: Vec4,
+        to: Vec4,
+        direction: Gradient,
+        name: &str,
+    ) -> BuilderTexture2D {
+        Self::from_fn((width, height), name, |x, y| {
+            let u = (x as f32 + 0.5) / width as f32;
+            let v = (y as f32 + 0.5) / height as f32;
+            let t = match direction {
+                Gradient::Horizontal => u,
+                Gradient::Vertical => v,
+                Gradient::Radial => ((u - 0.5).hypot(v - 0.5) * 2f32.sqrt()).min(1.),
+            };
+            lerp(from, to, t)
+        })
+    }
+
+    /// Magenta and black checkerboard drawn in place of textures that aren't registered.
+    pub fn missing(name: &str) -> BuilderTexture2D {
+        Self::checkerboard(
+            64,
+            8,
+            Vec4::new(1., 0., 1., 1.),
+            Vec4::new(0., 0., 0., 1.),
+            name,
+        )
+    }
+}
+
+fn to_rgba(color: Vec4) -> Rgba<u8> {
+    let c = |v: f32| (v.clamp(0., 1.) * 255.).round() as u8;
+    Rgba([c(color.x), c(color.y), c(color.z), c(color.w)])
+}
+
+fn lerp(a: Vec4, b: Vec4, t: f32) -> Vec4 {
+    Vec4::new(
+        a.x + (b.x - a.x) * t,
+        a.y + (b.y - a.y) * t,
+        a.z + (b.z - a.z) * t,
+        a.w + (b.w - a.w) * t,
+    )
+}
+
+/// Smoothly interpolated random values on an integer lattice, in `[0, 1]`.
+fn value_noise(x: f32, y: f32, seed: u32) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (tx, ty) = (smoothstep(x - x0), smoothstep(y - y0));
+    let (x0, y0) = (x0 as u32, y0 as u32);
+
+    let corner = |dx, dy| lattice_value(x0 + dx, y0 + dy, seed);
+    let top = corner(0, 0) + (corner(1, 0) - corner(0, 0)) * tx;
+    let bottom = corner(0, 1) + (corner(1, 1) - corner(0, 1)) * tx;
+    top + (bottom - top) * ty
+}
+
+fn lattice_value(x: u32, y: u32, seed: u32) -> f32 {
+    let mut h = seed ^ x.wrapping_mul(0x27d4_eb2d) ^ y.wrapping_mul(0x1656_67b1);
+    h = (h ^ (h >> 15)).wrapping_mul(0x85eb_ca6b);
+    h = (h ^ (h >> 13)).wrapping_mul(0xc2b2_ae35);
+    h ^= h >> 16;
+    h as f32 / u32::MAX as f32
+}
+
+fn smoothstep(t: f32) -> f32 {
+    t * t * (3. - 2. * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixels(texture: BuilderTexture2D) -> RgbaImage {
+        texture.into_image().to_rgba8()
+    }
+
+    #[test]
+    fn checkerboard_switches_color_at_cell_boundaries() {
+        let (a, b) = (Vec4::new(1., 0., 0., 1.), Vec4::new(0., 0., 1., 1.));
+        let img = pixels(Texture2D::checkerboard(16, 4, a, b, "checker"));
+        let (a, b) = (to_rgba(a), to_rgba(b));
+
+        assert_eq!(*img.get_pixel(0, 0), a);
+        assert_eq!(*img.get_pixel(3, 3), a);
+        assert_eq!(*img.get_pixel(4, 0), b);
+        assert_eq!(*img.get_pixel(0, 4), b);
+        assert_eq!(*img.get_pixel(4, 4), a);
+        assert_eq!(*img.get_pixel(15, 8), b);
+    }
+
+    #[test]
+    fn checkerboard_with_more_cells_than_pixels() {
+        let (a, b) = (Vec4::new(1., 1., 1., 1.), Vec4::new(0., 0., 0., 1.));
+        let img = pixels(Texture2D::checkerboard(2, 8, a, b, "checker"));
+
+        assert_eq!(*img.get_pixel(0, 0), to_rgba(a));
+        assert_eq!(*img.get_pixel(1, 0), to_rgba(b));
+    }
+
+    #[test]
+    fn horizontal_gradient_samples_pixel_centers() {
+        let (from, to) = (Vec4::new(0., 0., 0., 1.), Vec4::new(1., 1., 1., 1.));
+        let img = pixels(Texture2D::gradient(
+            (2, 1),
+            from,
+            to,
+            Gradient::Horizontal,
+            "gradient",
+        ));
+
+        assert_eq!(img.get_pixel(0, 0).0, [64, 64, 64, 255]);
+        assert_eq!(img.get_pixel(1, 0).0, [191, 191, 191, 255]);
+    }
+
+    #[test]
+    fn noise_is_deterministic_and_in_range() {
+        let a = pixels(Texture2D::noise(16, 4, 7, "noise"));
+        let b = pixels(Texture2D::noise(16, 4, 7, "noise"));
+        let c = pixels(Texture2D::noise(16, 4, 8, "noise"));
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert!(a.pixels().all(|p| p.0[3] == 255));
+    }
+}
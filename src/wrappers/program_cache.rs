@@ -0,0 +1,167 @@
+use std::{
+    ffi::CStr,
+    fs,
+    path::{Path, PathBuf},
+    ptr,
+};
+
+use gl::types::{GLenum, GLint};
+
+use super::types::ShaderType;
+
+/// On-disk cache of linked program binaries.
+///
+/// Entries are named by an FNV-1a hash of every shader stage source together with
+/// the driver vendor, renderer and version strings. The hashed data is stored in the
+/// entry's header and compared on load, so a driver update or a hash collision simply misses.
+/// Any failure while reading or writing the cache falls back to compiling from source.
+#[derive(Debug, Clone)]
+pub struct ProgramCache {
+    dir: PathBuf,
+}
+
+impl ProgramCache {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Drivers are allowed to expose no binary formats at all, in which case caching is skipped.
+    pub fn is_supported() -> bool {
+        let mut formats = 0;
+        unsafe { gl::GetIntegerv(gl::NUM_PROGRAM_BINARY_FORMATS, &mut formats) };
+        formats > 0
+    }
+
+    pub fn key(stages: &[(&str, ShaderType)]) -> CacheKey {
+        let mut identity = vec![];
+        stages.iter().for_each(|(source, shader_type)| {
+            identity.extend_from_slice(&(*shader_type as u32).to_le_bytes());
+            push_string(&mut identity, source);
+        });
+        [gl::VENDOR, gl::RENDERER, gl::VERSION]
+            .into_iter()
+            .for_each(|name| push_string(&mut identity, &driver_string(name)));
+
+        CacheKey {
+            hash: fnv1a(&identity),
+            identity,
+        }
+    }
+
+    /// Creates a program from a cached binary. Returns `None` if there is no
+    /// entry, it was built from other sources or drivers, or the driver rejected it.
+    pub fn load(&self, key: &CacheKey) -> Option<u32> {
+        let bytes = fs::read(self.entry(key)).ok()?;
+        let (identity_len, bytes) = split_u64(&bytes)?;
+        let identity_len = usize::try_from(identity_len).ok()?;
+        if bytes.len() < identity_len {
+            return None;
+        }
+        let (identity, bytes) = bytes.split_at(identity_len);
+        if identity != key.identity {
+            return None;
+        }
+
+        if bytes.len() <= 4 {
+            return None;
+        }
+        let (format, binary) = bytes.split_at(4);
+        let format = GLenum::from_le_bytes(format.try_into().unwrap());
+
+        unsafe {
+            let program = gl::CreateProgram();
+            gl::ProgramBinary(program, format, binary.as_ptr().cast(), binary.len() as _);
+
+            let mut success: GLint = 0;
+            gl::GetProgramiv(program, gl::LINK_STATUS, &mut success);
+            if success == 0 {
+                gl::DeleteProgram(program);
+                return None;
+            }
+
+            Some(program)
+        }
+    }
+
+    /// Writes the binary of a program linked with `PROGRAM_BINARY_RETRIEVABLE_HINT`.
+    pub fn store(&self, key: &CacheKey, program: u32) {
+        let mut length: GLint = 0;
+        unsafe { gl::GetProgramiv(program, gl::PROGRAM_BINARY_LENGTH, &mut length) };
+        if length <= 0 {
+            return;
+        }
+
+        let mut binary = vec![0u8; length as usize];
+        let mut format: GLenum = 0;
+        unsafe {
+            gl::GetProgramBinary(
+                program,
+                length,
+                ptr::null_mut(),
+                &mut format,
+                binary.as_mut_ptr().cast(),
+            );
+        }
+
+        let mut bytes = (key.identity.len() as u64).to_le_bytes().to_vec();
+        bytes.extend_from_slice(&key.identity);
+        bytes.extend_from_slice(&format.to_le_bytes());
+        bytes.append(&mut binary);
+
+        if fs::create_dir_all(&self.dir).is_ok() {
+            let _ = fs::write(self.entry(key), bytes);
+        }
+    }
+
+    fn entry(&self, key: &CacheKey) -> PathBuf {
+        self.dir.join(format!("{:016x}.bin", key.hash))
+    }
+}
+
+/// Identifies a program binary: what it was built from and the hash naming its entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheKey {
+    hash: u64,
+    /// Stage types and sources followed by the driver strings.
+    identity: Vec<u8>,
+}
+
+/// 64-bit FNV-1a, which unlike the std hashers is fixed across Rust releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(PRIME)
+    })
+}
+
+/// Length prefixed, so consecutive strings can't be confused with each other.
+fn push_string(bytes: &mut Vec<u8>, s: &str) {
+    bytes.extend_from_slice(&(s.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(s.as_bytes());
+}
+
+fn split_u64(bytes: &[u8]) -> Option<(u64, &[u8])> {
+    if bytes.len() < 8 {
+        return None;
+    }
+    let (value, rest) = bytes.split_at(8);
+    Some((u64::from_le_bytes(value.try_into().unwrap()), rest))
+}
+
+fn driver_string(name: GLenum) -> String {
+    unsafe {
+        let s = gl::GetString(name);
+        if s.is_null() {
+            return String::new();
+        }
+        CStr::from_ptr(s.cast()).to_string_lossy().into_owned()
+    }
+}
@@ -0,0 +1,138 @@
+use gl::types::{GLenum, GLint, GLsizei};
+
+use super::types::GlslType;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActiveAttribute {
+    pub name: String,
+    pub location: i32,
+    pub glsl_type: GlslType,
+    pub size: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActiveUniform {
+    pub name: String,
+    /// `-1` for members of uniform blocks.
+    pub location: i32,
+    pub glsl_type: GlslType,
+    pub size: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActiveUniformBlock {
+    pub name: String,
+    pub index: u32,
+    pub binding: i32,
+    pub data_size: i32,
+}
+
+pub(crate) unsafe fn active_attributes(program: u32) -> Vec<ActiveAttribute> {
+    let count = program_parameter(program, gl::ACTIVE_ATTRIBUTES);
+    let max_length = program_parameter(program, gl::ACTIVE_ATTRIBUTE_MAX_LENGTH);
+
+    (0..count as u32)
+        .map(|index| {
+            let (name, glsl_type, size) = read_active(max_length, |len, buf, size, ty| {
+                gl::GetActiveAttrib(program, index, max_length, len, size, ty, buf)
+            });
+            let c_name = std::ffi::CString::new(name.as_bytes()).unwrap();
+            let location = gl::GetAttribLocation(program, c_name.as_ptr());
+
+            ActiveAttribute {
+                name,
+                location,
+                glsl_type,
+                size,
+            }
+        })
+        .collect()
+}
+
+pub(crate) unsafe fn active_uniforms(program: u32) -> Vec<ActiveUniform> {
+    let count = program_parameter(program, gl::ACTIVE_UNIFORMS);
+    let max_length = program_parameter(program, gl::ACTIVE_UNIFORM_MAX_LENGTH);
+
+    (0..count as u32)
+        .map(|index| {
+            let (name, glsl_type, size) = read_active(max_length, |len, buf, size, ty| {
+                gl::GetActiveUniform(program, index, max_length, len, size, ty, buf)
+            });
+            let c_name = std::ffi::CString::new(name.as_bytes()).unwrap();
+            let location = gl::GetUniformLocation(program, c_name.as_ptr());
+
+            ActiveUniform {
+                name,
+                location,
+                glsl_type,
+                size,
+            }
+        })
+        .collect()
+}
+
+pub(crate) unsafe fn active_uniform_blocks(program: u32) -> Vec<ActiveUniformBlock> {
+    let count = program_parameter(program, gl::ACTIVE_UNIFORM_BLOCKS);
+    let max_length = program_parameter(program, gl::ACTIVE_UNIFORM_BLOCK_MAX_NAME_LENGTH);
+
+    (0..count as u32)
+        .map(|index| {
+            let mut name = vec![0u8; max_length.max(1) as usize];
+            let mut length: GLsizei = 0;
+            gl::GetActiveUniformBlockName(
+                program,
+                index,
+                max_length,
+                &mut length,
+                name.as_mut_ptr().cast(),
+            );
+            name.truncate(length as usize);
+
+            let mut binding = 0;
+            gl::GetActiveUniformBlockiv(program, index, gl::UNIFORM_BLOCK_BINDING, &mut binding);
+            let mut data_size = 0;
+            gl::GetActiveUniformBlockiv(
+                program,
+                index,
+                gl::UNIFORM_BLOCK_DATA_SIZE,
+                &mut data_size,
+            );
+
+            ActiveUniformBlock {
+                name: String::from_utf8_lossy(&name).into_owned(),
+                index,
+                binding,
+                data_size,
+            }
+        })
+        .collect()
+}
+
+unsafe fn program_parameter(program: u32, parameter: GLenum) -> GLint {
+    let mut value = 0;
+    gl::GetProgramiv(program, parameter, &mut value);
+    value
+}
+
+unsafe fn read_active<F>(max_length: GLint, query: F) -> (String, GlslType, i32)
+where
+    F: FnOnce(*mut GLsizei, *mut gl::types::GLchar, *mut GLint, *mut GLenum),
+{
+    let mut name = vec![0u8; max_length.max(1) as usize];
+    let mut length: GLsizei = 0;
+    let mut size: GLint = 0;
+    let mut glsl_type: GLenum = 0;
+    query(
+        &mut length,
+        name.as_mut_ptr().cast(),
+        &mut size,
+        &mut glsl_type,
+    );
+    name.truncate(length as usize);
+
+    (
+        String::from_utf8_lossy(&name).into_owned(),
+        glsl_type.into(),
+        size,
+    )
+}
@@ -0,0 +1,328 @@
+use std::ptr;
+
+use gl::types::{GLenum, GLuint};
+
+use crate::object::components::Transform;
+
+use super::{
+    buffer_object::BufferObject,
+    culling::Frustum,
+    material::{BlendMode, Material},
+    mesh::{BoundStaticMesh, InstanceData},
+    shader::Shader,
+    shadows::{ALPHA_MAP, ALPHA_TEST_SAMPLERS},
+    textures::Texture,
+    types::Uniform,
+};
+
+/// Work the renderer submitted over one frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    pub draw_calls: usize,
+    /// Objects drawn, an instanced draw call counts each of its instances.
+    pub objects: usize,
+    pub program_changes: usize,
+    pub texture_binds: usize,
+    pub mesh_binds: usize,
+    /// Objects skipped for being outside the camera's view.
+    pub culled: usize,
+}
+
+impl RenderStats {
+    /// Program, texture and mesh binds combined.
+    pub fn state_changes(&self) -> usize {
+        self.program_changes + self.texture_binds + self.mesh_binds
+    }
+}
+
+/// Remembers the program, vertex array and textures bound through it so binding
+/// them again is skipped, and counts what was actually bound.
+///
+/// Only accurate while nothing else binds those objects,
+/// call [`Self::invalidate`] after code that might.
+#[derive(Debug, Default)]
+pub struct StateCache {
+    program: Option<GLuint>,
+    vertex_array: Option<GLuint>,
+    /// Target and texture bound to each unit.
+    textures: Vec<Option<(GLenum, GLuint)>>,
+    stats: RenderStats,
+}
+
+impl StateCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns whether the program had to be changed.
+    pub fn use_program(&mut self, shader: &Shader) -> bool {
+        if self.program == Some(shader.id()) {
+            return false;
+        }
+
+        shader.use_program();
+        self.program = Some(shader.id());
+        self.stats.program_changes += 1;
+        true
+    }
+
+    pub fn bind_mesh(&mut self, mesh: &BoundStaticMesh) {
+        if self.vertex_array == Some(mesh.id()) {
+            return;
+        }
+
+        mesh.bind();
+        self.vertex_array = Some(mesh.id());
+        self.stats.mesh_binds += 1;
+    }
+
+    pub fn bind_texture(&mut self, unit: usize, texture: &dyn Texture) {
+        let binding = Some((texture.target(), texture.id()));
+        if unit >= self.textures.len() {
+            self.textures.resize(unit + 1, None);
+        }
+        if self.textures[unit] == binding {
+            return;
+        }
+
+        texture.bind(unit.into());
+        self.textures[unit] = binding;
+        self.stats.texture_binds += 1;
+    }
+
+    /// Forgets every binding, the next ones are always issued.
+    pub fn invalidate(&mut self) {
+        self.program = None;
+        self.vertex_array = None;
+        self.textures.clear();
+    }
+
+    fn record_draw(&mut self, objects: usize) {
+        self.stats.draw_calls += 1;
+        self.stats.objects += objects;
+    }
+
+    pub fn stats(&self) -> RenderStats {
+        self.stats
+    }
+}
+
+/// An object resolved to everything needed to draw it.
+#[derive(Debug, Clone)]
+pub struct DrawItem<'a> {
+    pub shader: &'a Shader,
+    /// `None` draws with the shader's current uniforms, without blending.
+    pub material: Option<&'a Material>,
+    /// Bound to consecutive texture units, in order, and assigned to the named samplers.
+    /// Must fit in [`TextureUnit::max_units`](super::types::TextureUnit::max_units),
+    /// the engine reports and drops the samplers past it.
+    pub textures: Vec<(&'a str, &'a dyn Texture)>,
+    pub mesh: &'a BoundStaticMesh,
+    pub transform: &'a Transform,
+    /// Squared distance to the camera.
+    pub distance: f32,
+}
+
+impl DrawItem<'_> {
+    /// Same program, material and textures, so only the mesh may need binding between the two.
+    fn same_pipeline(&self, other: &DrawItem) -> bool {
+        let same_material = match (self.material, other.material) {
+            (Some(a), Some(b)) => ptr::eq(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+        let same_textures =
+            self.textures.len() == other.textures.len()
+                && self.textures.iter().zip(&other.textures).all(
+                    |((a_sampler, a), (b_sampler, b))| a_sampler == b_sampler && a.id() == b.id(),
+                );
+
+        self.shader.id() == other.shader.id() && same_material && same_textures
+    }
+
+    fn shares_state(&self, other: &DrawItem) -> bool {
+        self.same_pipeline(other) && self.mesh.id() == other.mesh.id()
+    }
+
+    /// Materials aren't part of the key, those sharing a program, textures and mesh
+    /// are only told apart by distance so the order doesn't depend on where they're allocated.
+    fn state_key(&self) -> (u32, GLuint, Vec<GLuint>, GLuint) {
+        let textures = self.textures.iter().map(|(_, t)| t.id()).collect();
+        (
+            depth_bucket(self.distance),
+            self.shader.id(),
+            textures,
+            self.mesh.id(),
+        )
+    }
+}
+
+/// Each bucket covers twice the distance of the previous one, `squared_distance` being
+/// the squared distance to the camera.
+fn depth_bucket(squared_distance: f32) -> u32 {
+    // Halving the logarithm of the squared distance gives the distance's.
+    (squared_distance.max(1.).log2() / 2.) as u32
+}
+
+/// Draw items collected over a frame, sorted then submitted in as few
+/// state changes and draw calls as their order allows.
+#[derive(Debug, Default)]
+pub struct RenderQueue<'a> {
+    items: Vec<DrawItem<'a>>,
+}
+
+impl<'a> RenderQueue<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, item: DrawItem<'a>) {
+        self.items.push(item);
+    }
+
+    pub fn items(&self) -> &[DrawItem<'a>] {
+        &self.items
+    }
+
+    /// Front to back in coarse depth buckets, so early depth testing still rejects hidden
+    /// fragments, and grouped by program, textures and mesh within a bucket to save binds
+    /// and draw calls. Nearest first within a group.
+    pub fn sort_by_state(&mut self) {
+        // Bits of non-negative floats order like the floats themselves.
+        self.items
+            .sort_by_cached_key(|item| (item.state_key(), item.distance.to_bits()));
+    }
+
+    /// Drops items whose bounds are outside `frustum`, returns how many.
+    pub fn cull(&mut self, frustum: &Frustum) -> usize {
+        let len = self.items.len();
+        self.items.retain(|item| {
+            let sphere = item.mesh.bounds().sphere().transformed(item.transform);
+            frustum.intersects(&sphere)
+        });
+        len - self.items.len()
+    }
+
+    /// Farthest items first, as blending needs.
+    pub fn sort_back_to_front(&mut self) {
+        self.items.sort_by(|a, b| b.distance.total_cmp(&a.distance));
+    }
+
+    /// The same items drawn with `shader` only, for depth passes. Items with an
+    /// [`AlphaTest`](BlendMode::AlphaTest) material and one of the [`ALPHA_TEST_SAMPLERS`]
+    /// are drawn with `alpha_test_shader` instead, that texture bound as [`ALPHA_MAP`].
+    pub fn depth_only<'b>(
+        &self,
+        shader: &'b Shader,
+        alpha_test_shader: &'b Shader,
+    ) -> RenderQueue<'b>
+    where
+        'a: 'b,
+    {
+        let items = self
+            .items
+            .iter()
+            .map(|item| {
+                let alpha_map = item
+                    .material
+                    .filter(|m| matches!(m.blend_mode(), BlendMode::AlphaTest { .. }))
+                    .and_then(|_| {
+                        item.textures
+                            .iter()
+                            .find(|(sampler, _)| ALPHA_TEST_SAMPLERS.contains(sampler))
+                    });
+                match alpha_map {
+                    // The material sets `alphaCutoff`, the depth shader ignores its other uniforms.
+                    Some((_, texture)) => DrawItem {
+                        shader: alpha_test_shader,
+                        textures: vec![(ALPHA_MAP, *texture)],
+                        ..item.clone()
+                    },
+                    None => DrawItem {
+                        shader,
+                        material: None,
+                        textures: vec![],
+                        ..item.clone()
+                    },
+                }
+            })
+            .collect();
+        RenderQueue { items }
+    }
+
+    /// Draws the items in order. Neighbours sharing their state are drawn in one
+    /// instanced call when the shader reads [`InstanceData`], and bindings that are
+    /// already in place are skipped.
+    pub fn submit(&self, cache: &mut StateCache, material_block: &BufferObject) {
+        let mut previous: Option<&DrawItem> = None;
+        let mut start = 0;
+        while start < self.items.len() {
+            let item = &self.items[start];
+            let len = self.items[start..]
+                .iter()
+                .take_while(|other| item.shares_state(other))
+                .count();
+            let batch = &self.items[start..start + len];
+            start += len;
+
+            let program_changed = cache.use_program(item.shader);
+            let pipeline_changed = !previous.is_some_and(|p| p.same_pipeline(item));
+            if program_changed || pipeline_changed {
+                match item.material {
+                    Some(material) => material.apply(item.shader, material_block),
+                    None => BlendMode::Opaque.apply(),
+                }
+            }
+
+            item.textures
+                .iter()
+                .enumerate()
+                .for_each(|(unit, (sampler, texture))| {
+                    cache.bind_texture(unit, *texture);
+                    if program_changed || pipeline_changed {
+                        item.shader
+                            .set_uniform_bound(sampler, Uniform::Int(unit as _));
+                    }
+                });
+
+            cache.bind_mesh(item.mesh);
+            if item.shader.accepts_instances() {
+                let instances: Vec<_> = batch
+                    .iter()
+                    .map(|item| InstanceData::new(item.transform.model_matrix()))
+                    .collect();
+                match item.shader.draw_instanced_bound(item.mesh, &instances) {
+                    Ok(()) => cache.record_draw(batch.len()),
+                    Err(e) => eprintln!("Skipped drawing {} objects: {e}", batch.len()),
+                }
+            } else {
+                batch.iter().for_each(|other| {
+                    match item.shader.draw_bound(other.transform, item.mesh) {
+                        Ok(()) => cache.record_draw(1),
+                        Err(e) => eprintln!("Skipped drawing an object: {e}"),
+                    }
+                });
+            }
+
+            previous = Some(item);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn depth_buckets_double_in_distance() {
+        let bucket = |distance: f32| depth_bucket(distance * distance);
+
+        assert_eq!(bucket(0.), 0);
+        assert_eq!(bucket(1.9), 0);
+        assert_eq!(bucket(2.), 1);
+        assert_eq!(bucket(3.9), 1);
+        assert_eq!(bucket(4.), 2);
+        assert_eq!(bucket(100.), 6);
+        assert!(bucket(10.) <= bucket(11.));
+    }
+}
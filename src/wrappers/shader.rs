@@ -1,28 +1,40 @@
-use std::{ffi::CString, fmt::Display, fs, io, mem, path::Path, ptr};
-
-use engine_math::{
-    transform::homogeneous::{lookat3, rotate3, scale3, translate3},
-    Matrix4, Vector, Vector3,
+use std::{
+    any::TypeId, cell::RefCell, collections::HashMap, ffi::CString, fmt::Display, fs, io, mem,
+    path::Path, ptr,
 };
-use gl::types::GLint;
+
+use gl::types::{GLint, GLuint};
 
 use crate::{
-    mesh::{BoundStaticMesh, Draw},
+    mesh::{BoundStaticMesh, InstanceData, INSTANCE_MODEL_ATTRIBUTE},
     object::components::Transform,
-    textures::Texture2D,
+    textures::Texture,
     types::TextureUnit,
     wrappers::to_ptr,
 };
 
 use super::{
+    attribute_pointer::{AttributePointers, Attributes},
     gl::GL,
-    types::{ProgramStatus, ShaderStatus, ShaderType, Uniform},
+    program_cache::ProgramCache,
+    reflection::{self, ActiveAttribute, ActiveUniform, ActiveUniformBlock},
+    types::{PrimitiveType, ProgramStatus, ShaderStatus, ShaderType, Uniform},
 };
 
 #[derive(Debug, Clone)]
 pub struct ShaderSource {
     vertex_shader: String,
     fragment_shader: String,
+    geometry_shader: Option<String>,
+    tessellation: Option<TessellationSource>,
+    cache: Option<ProgramCache>,
+}
+
+#[derive(Debug, Clone)]
+struct TessellationSource {
+    control_shader: String,
+    evaluation_shader: String,
+    patch_vertices: i32,
 }
 
 impl ShaderSource {
@@ -31,6 +43,9 @@ impl ShaderSource {
         Self {
             vertex_shader,
             fragment_shader,
+            geometry_shader: None,
+            tessellation: None,
+            cache: None,
         }
     }
 
@@ -41,61 +56,192 @@ impl ShaderSource {
         let vertex_shader = fs::read_to_string(vertex_shader_path)?;
         let fragment_shader = fs::read_to_string(fragment_shader_path)?;
 
-        Ok(Self {
-            vertex_shader,
-            fragment_shader,
-        })
+        Ok(Self::from_strings(vertex_shader, fragment_shader))
+    }
+
+    pub fn set_geometry_shader(mut self, geometry_shader: String) -> Self {
+        self.geometry_shader = Some(geometry_shader);
+        self
+    }
+
+    pub fn geometry_shader_from_file<P>(self, path: P) -> Result<Self, io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(self.set_geometry_shader(fs::read_to_string(path)?))
+    }
+
+    /// Adds tessellation control and evaluation stages.
+    /// Meshes drawn with the resulting program are submitted as patches of `patch_vertices`.
+    pub fn set_tessellation_shaders(
+        mut self,
+        control_shader: String,
+        evaluation_shader: String,
+        patch_vertices: i32,
+    ) -> Self {
+        self.tessellation = Some(TessellationSource {
+            control_shader,
+            evaluation_shader,
+            patch_vertices,
+        });
+        self
+    }
+
+    pub fn tessellation_shaders_from_files<P>(
+        self,
+        control_shader_path: P,
+        evaluation_shader_path: P,
+        patch_vertices: i32,
+    ) -> Result<Self, io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let control_shader = fs::read_to_string(control_shader_path)?;
+        let evaluation_shader = fs::read_to_string(evaluation_shader_path)?;
+
+        Ok(self.set_tessellation_shaders(control_shader, evaluation_shader, patch_vertices))
+    }
+
+    /// Reuses linked binaries from `cache` instead of compiling when possible.
+    pub fn set_cache(mut self, cache: ProgramCache) -> Self {
+        self.cache = Some(cache);
+        self
     }
 
     pub fn compile(self, _: &GL) -> Shader {
+        let mut stages = vec![
+            (self.vertex_shader.as_str(), ShaderType::VertexShader),
+            (self.fragment_shader.as_str(), ShaderType::FragmentShader),
+        ];
+
+        if let Some(geometry_shader) = &self.geometry_shader {
+            stages.push((geometry_shader, ShaderType::GeometryShader));
+        }
+
+        if let Some(tessellation) = &self.tessellation {
+            stages.push((&tessellation.control_shader, ShaderType::TessControlShader));
+            stages.push((
+                &tessellation.evaluation_shader,
+                ShaderType::TessEvaluationShader,
+            ));
+        }
+
         unsafe {
-            let vertex_shader = gl::CreateShader(gl::VERTEX_SHADER);
-            let vertex_shader_source = CString::new(self.vertex_shader.as_bytes()).unwrap();
-            gl::ShaderSource(
-                vertex_shader,
-                1,
-                &vertex_shader_source.as_ptr(),
-                ptr::null(),
-            );
-            gl::CompileShader(vertex_shader);
-
-            Shader::get_shader_status(
-                vertex_shader,
-                ShaderStatus::CompileStatus,
-                ShaderType::VertexShader,
-            )
-            .unwrap();
-
-            let fragment_shader = gl::CreateShader(gl::FRAGMENT_SHADER);
-            let fragment_shader_source = CString::new(self.fragment_shader.as_bytes()).unwrap();
-            gl::ShaderSource(
-                fragment_shader,
-                1,
-                &fragment_shader_source.as_ptr(),
-                ptr::null(),
-            );
-            gl::CompileShader(fragment_shader);
-
-            Shader::get_shader_status(
-                fragment_shader,
-                ShaderStatus::CompileStatus,
-                ShaderType::FragmentShader,
+            Shader::from_program(
+                build_program(&stages, self.cache.as_ref()),
+                self.tessellation.as_ref().map(|t| t.patch_vertices),
             )
-            .unwrap();
+        }
+    }
+}
 
-            let shader_program = gl::CreateProgram();
-            gl::AttachShader(shader_program, vertex_shader);
-            gl::AttachShader(shader_program, fragment_shader);
-            gl::LinkProgram(shader_program);
+#[derive(Debug, Clone)]
+pub struct ComputeSource {
+    compute_shader: String,
+    cache: Option<ProgramCache>,
+}
 
-            Shader::get_program_status(shader_program, ProgramStatus::LinkStatus).unwrap();
+impl ComputeSource {
+    pub fn from_string(compute_shader: String) -> Self {
+        Self {
+            compute_shader,
+            cache: None,
+        }
+    }
 
-            gl::DeleteShader(vertex_shader);
-            gl::DeleteShader(fragment_shader);
+    pub fn from_file<P>(compute_shader_path: P) -> Result<Self, io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(Self::from_string(fs::read_to_string(compute_shader_path)?))
+    }
 
-            Shader {
-                shader: shader_program,
-            }
+    pub fn set_cache(mut self, cache: ProgramCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    pub fn compile(self, _: &GL) -> ComputeShader {
+        let stages = [(self.compute_shader.as_str(), ShaderType::ComputeShader)];
+
+        ComputeShader {
+            program: unsafe {
+                Shader::from_program(build_program(&stages, self.cache.as_ref()), None)
+            },
+        }
+    }
+}
+
+unsafe fn build_program(stages: &[(&str, ShaderType)], cache: Option<&ProgramCache>) -> u32 {
+    let cache = cache
+        .filter(|_| ProgramCache::is_supported())
+        .map(|cache| (cache, ProgramCache::key(stages)));
+
+    if let Some(program) = cache.as_ref().and_then(|(cache, key)| cache.load(key)) {
+        return program;
+    }
+
+    let compiled: Vec<_> = stages
+        .iter()
+        .map(|(source, shader_type)| compile_stage(source, *shader_type))
+        .collect();
+    let program = link_program(&compiled, cache.is_some());
+
+    if let Some((cache, key)) = cache {
+        cache.store(&key, program);
+    }
+
+    program
+}
+
+unsafe fn compile_stage(source: &str, shader_type: ShaderType) -> u32 {
+    let shader = gl::CreateShader(shader_type as _);
+    let source = CString::new(source.as_bytes()).unwrap();
+    gl::ShaderSource(shader, 1, &source.as_ptr(), ptr::null());
+    gl::CompileShader(shader);
+
+    Shader::get_shader_status(shader, ShaderStatus::CompileStatus, shader_type).unwrap();
+
+    shader
+}
+
+unsafe fn link_program(stages: &[u32], retrievable: bool) -> u32 {
+    let shader_program = gl::CreateProgram();
+    if retrievable {
+        gl::ProgramParameteri(
+            shader_program,
+            gl::PROGRAM_BINARY_RETRIEVABLE_HINT,
+            gl::TRUE as _,
+        );
+    }
+    stages
+        .iter()
+        .for_each(|stage| gl::AttachShader(shader_program, *stage));
+    gl::LinkProgram(shader_program);
+
+    Shader::get_program_status(shader_program, ProgramStatus::LinkStatus).unwrap();
+
+    stages.iter().for_each(|stage| gl::DeleteShader(*stage));
+
+    shader_program
+}
+
+/// Standalone compute program, dispatched outside of the draw loop.
+#[derive(Debug)]
+pub struct ComputeShader {
+    program: Shader,
+}
+
+impl ComputeShader {
+    /// Underlying program, used to set uniforms and bind uniform blocks.
+    pub fn program(&self) -> &Shader {
+        &self.program
+    }
+
+    pub fn dispatch(&self, groups_x: u32, groups_y: u32, groups_z: u32) {
+        self.program.use_program();
+        unsafe {
+            gl::DispatchCompute(groups_x, groups_y, groups_z);
         }
     }
 }
@@ -103,45 +249,219 @@ impl ShaderSource {
 #[derive(Debug)]
 pub struct Shader {
     shader: u32,
+    patch_vertices: Option<i32>,
+    attributes: Vec<ActiveAttribute>,
+    uniforms: Vec<ActiveUniform>,
+    /// Bindings are kept up to date by [`Self::bind_uniform_block`].
+    uniform_blocks: RefCell<Vec<ActiveUniformBlock>>,
+    /// Outcome of [`Self::validate_attributes`] per mesh and instance type drawn,
+    /// so each layout is only checked on its first draw.
+    layouts: RefCell<HashMap<LayoutKey, Result<(), Error>>>,
 }
 
+/// Vertex array of the mesh drawn, and the instance type if drawn instanced.
+type LayoutKey = (GLuint, Option<TypeId>);
+
 impl Shader {
-    pub fn draw(
+    unsafe fn from_program(shader: u32, patch_vertices: Option<i32>) -> Self {
+        Self {
+            shader,
+            patch_vertices,
+            attributes: reflection::active_attributes(shader),
+            uniforms: reflection::active_uniforms(shader),
+            uniform_blocks: RefCell::new(reflection::active_uniform_blocks(shader)),
+            layouts: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.shader
+    }
+
+    /// Fails without drawing if `mesh` doesn't feed every input of the program.
+    /// Programs that [accept instances](Self::accepts_instances) get `transform`
+    /// as a single instance instead of the `model` uniform.
+    pub fn draw(&self, transform: &Transform, mesh: &BoundStaticMesh) -> Result<(), Error> {
+        self.use_program();
+        mesh.bind();
+        self.draw_bound(transform, mesh)
+    }
+
+    /// [`Self::draw`] for callers that already bound the program and `mesh`,
+    /// see [`StateCache`](super::render_queue::StateCache).
+    pub fn draw_bound(&self, transform: &Transform, mesh: &BoundStaticMesh) -> Result<(), Error> {
+        if self.accepts_instances() {
+            let instance = InstanceData::new(transform.model_matrix());
+            return self.draw_instanced_bound(mesh, &[instance]);
+        }
+
+        self.check_layout(mesh, None, || mesh.attributes().to_vec())?;
+
+        self.set_uniform_bound("model", Uniform::Matrix4(transform.model_matrix()));
+
+        let primitive = self.primitive();
+        mesh.draw_bound(primitive);
+        Ok(())
+    }
+
+    /// Draws `mesh` once per element of `instances` in a single call.
+    /// The program reads per-instance data from the inputs `T` lays out
+    /// instead of the `model` uniform.
+    pub fn draw_instanced<T: Attributes + 'static>(
         &self,
-        transform: &Transform,
         mesh: &BoundStaticMesh,
-        texture: Option<&Texture2D>,
-        projection: Matrix4,
-    ) {
+        instances: &[T],
+    ) -> Result<(), Error> {
         self.use_program();
-        self.set_uniform("projection", Uniform::Matrix4(projection));
-
-        let Vector3 { x, y, z } = transform.rotation;
-        let model = translate3(transform.pos) * scale3(transform.scale) * rotate3(x, y, z);
-        let view = lookat3(
-            Vector3::new(0., 0., 3.),
-            Vector3::zero(),
-            Vector3::new(0., 1., 0.),
-        );
+        mesh.bind();
+        self.draw_instanced_bound(mesh, instances)
+    }
+
+    /// [`Self::draw_instanced`] for callers that already bound the program and `mesh`.
+    pub fn draw_instanced_bound<T: Attributes + 'static>(
+        &self,
+        mesh: &BoundStaticMesh,
+        instances: &[T],
+    ) -> Result<(), Error> {
+        self.check_layout(mesh, Some(TypeId::of::<T>()), || {
+            [mesh.attributes(), &T::get_attributes()].concat()
+        })?;
+
+        if !instances.is_empty() {
+            let primitive = self.primitive();
+            mesh.draw_instanced(instances, primitive);
+        }
+        Ok(())
+    }
 
-        self.set_uniform("model", Uniform::Matrix4(model));
-        self.set_uniform("view", Uniform::Matrix4(view));
+    /// Validates the layout `mesh` and the `instances` type provide on their first draw only.
+    fn check_layout<F>(
+        &self,
+        mesh: &BoundStaticMesh,
+        instances: Option<TypeId>,
+        layout: F,
+    ) -> Result<(), Error>
+    where
+        F: FnOnce() -> Vec<AttributePointers>,
+    {
+        self.layouts
+            .borrow_mut()
+            .entry((mesh.id(), instances))
+            .or_insert_with(|| self.validate_attributes(&layout()))
+            .clone()
+    }
 
-        texture.map(|t| t.bind(TextureUnit::Texture0));
+    /// Whether the program takes its model matrix from the per-instance
+    /// `instanceModel` input, see [`InstanceData`](super::mesh::InstanceData).
+    pub fn accepts_instances(&self) -> bool {
+        self.attributes
+            .iter()
+            .any(|a| a.name == INSTANCE_MODEL_ATTRIBUTE)
+    }
 
-        mesh.draw();
+    /// Primitive meshes are submitted as, setting the patch size for tessellated programs.
+    fn primitive(&self) -> PrimitiveType {
+        match self.patch_vertices {
+            Some(patch_vertices) => {
+                unsafe { gl::PatchParameteri(gl::PATCH_VERTICES, patch_vertices) };
+                PrimitiveType::Patches
+            }
+            None => PrimitiveType::Triangles,
+        }
     }
 
-    fn use_program(&self) {
+    pub(crate) fn use_program(&self) {
         unsafe { gl::UseProgram(self.shader) };
     }
 
+    /// Vertex inputs consumed by the program, built-ins excluded.
+    pub fn attributes(&self) -> &[ActiveAttribute] {
+        &self.attributes
+    }
+
+    /// Uniforms as reflected when the program was linked.
+    pub fn uniforms(&self) -> &[ActiveUniform] {
+        &self.uniforms
+    }
+
+    /// Uniform blocks as reflected when the program was linked.
+    pub fn uniform_blocks(&self) -> Vec<ActiveUniformBlock> {
+        self.uniform_blocks.borrow().clone()
+    }
+
+    /// Checks that a vertex layout feeds every input of the program.
+    /// Component counts must match, except that `vec4` inputs also accept three
+    /// components with `w` defaulting to one.
+    pub fn validate_attributes(&self, layout: &[AttributePointers]) -> Result<(), Error> {
+        self.attributes
+            .iter()
+            .filter(|a| a.location >= 0)
+            .try_for_each(|attribute| {
+                let provided = layout
+                    .iter()
+                    .find(|p| p.location as i32 == attribute.location)
+                    .ok_or_else(|| {
+                        Error::AttributeMismatch(format!(
+                            "Attribute `{}` ({}) at location {} is not provided by the mesh",
+                            attribute.name, attribute.glsl_type, attribute.location
+                        ))
+                    })?;
+
+                let expected = attribute.glsl_type.components();
+                let compatible = match expected {
+                    Some(4) => provided.size == 3 || provided.size == 4,
+                    Some(n) => provided.size == n,
+                    None => true,
+                };
+
+                if !compatible {
+                    return Err(Error::AttributeMismatch(format!(
+                        "Attribute `{}` at location {} expects {} but the mesh provides {} components",
+                        attribute.name, attribute.location, attribute.glsl_type, provided.size
+                    )));
+                }
+
+                Ok(())
+            })
+    }
+
+    /// Binds each texture to its own unit, in order, and points the sampler uniform at it.
+    /// Samplers past the available units are logged and left unbound.
+    pub fn bind_textures<'a, I>(&self, textures: I)
+    where
+        I: IntoIterator<Item = (&'a str, &'a dyn Texture)>,
+    {
+        TextureUnit::limit(textures)
+            .into_iter()
+            .enumerate()
+            .for_each(|(unit, (sampler, texture))| {
+                texture.bind(unit.into());
+                self.set_uniform(sampler, Uniform::Int(unit as _));
+            });
+    }
+
+    /// Assigns the uniform block `name` to `binding`.
+    /// Programs that don't declare the block are left untouched.
+    pub fn bind_uniform_block(&self, name: &str, binding: u32) {
+        let mut blocks = self.uniform_blocks.borrow_mut();
+        if let Some(block) = blocks.iter_mut().find(|block| block.name == name) {
+            unsafe { gl::UniformBlockBinding(self.shader, block.index, binding) };
+            block.binding = binding as _;
+        }
+    }
+
     pub fn set_uniform(&self, name: &str, uniform: Uniform) {
+        self.use_program();
+        self.set_uniform_bound(name, uniform);
+    }
+
+    /// [`Self::set_uniform`] for callers that already made this the current program,
+    /// see [`StateCache`](super::render_queue::StateCache).
+    pub fn set_uniform_bound(&self, name: &str, uniform: Uniform) {
         use engine_math::Matrix;
 
         unsafe {
             let name = CString::new(name).unwrap();
-            self.use_program();
             let location = gl::GetUniformLocation(self.shader, name.as_ptr());
             match uniform {
                 Uniform::Vector4(v) => gl::Uniform4f(location, v.x, v.y, v.z, v.w),
@@ -197,10 +517,11 @@ impl Shader {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Error {
     CompilationError(String),
     LinkingError(String),
+    AttributeMismatch(String),
 }
 
 impl Display for Error {
@@ -211,6 +532,7 @@ impl Display for Error {
             match self {
                 Error::CompilationError(s) => s,
                 Error::LinkingError(s) => s,
+                Error::AttributeMismatch(s) => s,
             }
         )
     }
@@ -0,0 +1,423 @@
+use engine_math::{
+    transform::homogeneous::{lookat3, perspective3, scale3, translate3},
+    Vector4,
+};
+use gl::types::GLuint;
+
+use crate::object::components::{Light, LightKind, Shadows};
+
+use super::{
+    gl::GL,
+    lighting::LightsBlock,
+    math::{add, cross, dot, length, mul, normalize, sub},
+    shader::{Shader, ShaderSource},
+    textures::{Texture, Texture2DArray, TextureParameters},
+    types::{FilterOptions, Mat4, PixelFormat, Uniform, Vec3, Vec4},
+    uniform_buffer::UniformBlock,
+};
+
+const VERTEX_SHADER: &str = include_str!("../../shaders/shadow_depth_vertex.glsl");
+const FRAGMENT_SHADER: &str = include_str!("../../shaders/shadow_depth_fragment.glsl");
+const ALPHA_TEST_FRAGMENT_SHADER: &str =
+    include_str!("../../shaders/shadow_depth_alpha_fragment.glsl");
+
+/// Name the lit and PBR shaders sample shadow maps under.
+pub const SHADOW_MAPS: &str = "shadow_maps";
+/// Shadow map layers rendered per frame, shared by all lights.
+/// Must match `MAX_SHADOW_MAPS` in the lit shaders.
+pub const MAX_SHADOW_MAPS: usize = 8;
+pub const MAX_CASCADES: u32 = 4;
+/// Samplers of the built-in lit and PBR shaders whose alpha cuts out
+/// [`AlphaTest`](super::material::BlendMode::AlphaTest) shadow casters.
+pub const ALPHA_TEST_SAMPLERS: [&str; 2] = ["diffuseMap", "albedoMap"];
+/// Sampler of the alpha-tested depth shader.
+pub const ALPHA_MAP: &str = "alphaMap";
+
+/// Blend between uniform and logarithmic cascade splits, higher favours detail near the camera.
+const CASCADE_SPLIT_LAMBDA: f32 = 0.75;
+const SPOT_NEAR_PLANE: f32 = 0.1;
+const SPOT_MAX_DISTANCE: f32 = 1000.;
+
+/// Engine-wide shadow settings, per-light ones live in [`Shadows`].
+#[derive(Debug, Clone, Copy)]
+pub struct ShadowQuality {
+    /// Width and height of every shadow map.
+    pub resolution: u32,
+    /// PCF kernel radius in texels, `0` gives hard shadows.
+    pub pcf_radius: u32,
+}
+
+impl Default for ShadowQuality {
+    fn default() -> Self {
+        Self {
+            resolution: 2048,
+            pcf_radius: 1,
+        }
+    }
+}
+
+/// What the shadow pass and culling need to know about the camera.
+#[derive(Debug, Clone, Copy)]
+pub struct CameraView {
+    pub position: Vec3,
+    pub target: Vec3,
+    pub up: Vec3,
+    /// Vertical field of view in degrees.
+    pub fov: f32,
+    pub aspect: f32,
+    pub near: f32,
+    pub far: f32,
+}
+
+/// One shadow map layer.
+#[derive(Debug, Clone, Copy)]
+pub struct ShadowView {
+    pub light_space: Mat4,
+    /// View depth up to which a cascade is used, unused by spot lights.
+    pub split: f32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct ShadowsBlock {
+    pub light_space: [Mat4; MAX_SHADOW_MAPS],
+    /// `x` is the cascade split.
+    pub splits: [Vec4; MAX_SHADOW_MAPS],
+    /// `x` is the PCF radius.
+    pub params: Vec4,
+}
+
+impl ShadowsBlock {
+    /// Keeps the first [`MAX_SHADOW_MAPS`] of `views`.
+    pub fn new(views: &[ShadowView], quality: ShadowQuality) -> Self {
+        use engine_math::Matrix;
+
+        let mut block = Self {
+            light_space: [scale3(Vec3::from(1.)); MAX_SHADOW_MAPS],
+            splits: [Vec4::new(0., 0., 0., 0.); MAX_SHADOW_MAPS],
+            params: Vec4::new(quality.pcf_radius as f32, 0., 0., 0.),
+        };
+        views
+            .iter()
+            .take(MAX_SHADOW_MAPS)
+            .enumerate()
+            .for_each(|(i, view)| {
+                block.light_space[i] = view.light_space.transpose();
+                block.splits[i] = Vec4::new(view.split, 0., 0., 0.);
+            });
+
+        block
+    }
+}
+
+unsafe impl UniformBlock for ShadowsBlock {
+    const NAME: &'static str = "Shadows";
+}
+
+/// Gives every shadow casting light in `lights` its shadow map layers, in order,
+/// until [`MAX_SHADOW_MAPS`] are taken, and records them in `block`.
+/// `lights` must be in the order they were added to `block`.
+pub fn assign_shadow_maps(
+    lights: &[(Vec3, Light)],
+    camera: &CameraView,
+    resolution: u32,
+    block: &mut LightsBlock,
+) -> Vec<ShadowView> {
+    let mut views = vec![];
+    lights
+        .iter()
+        .enumerate()
+        .take(block.count as usize)
+        .for_each(|(i, (position, light))| {
+            let Some(shadows) = light.shadows else {
+                return;
+            };
+            let light_views = match light.kind {
+                LightKind::Directional { direction } => {
+                    directional_views(direction, &shadows, camera, resolution)
+                }
+                LightKind::Spot {
+                    direction,
+                    attenuation,
+                    outer_angle,
+                    ..
+                } => {
+                    let far = attenuation.cutoff_distance().min(SPOT_MAX_DISTANCE);
+                    vec![spot_view(*position, direction, outer_angle, far)]
+                }
+                LightKind::Point { .. } => return,
+            };
+
+            if views.len() + light_views.len() > MAX_SHADOW_MAPS {
+                return;
+            }
+            block.lights[i].shadow = Vec4::new(
+                views.len() as f32,
+                light_views.len() as f32,
+                shadows.bias,
+                shadows.normal_bias,
+            );
+            views.extend(light_views);
+        });
+
+    views
+}
+
+/// Cascades covering the camera's view up to `shadows.distance`, each fitted
+/// in a bounding sphere snapped to shadow map texels so shadows don't shimmer as the camera moves.
+pub fn directional_views(
+    direction: Vec3,
+    shadows: &Shadows,
+    camera: &CameraView,
+    resolution: u32,
+) -> Vec<ShadowView> {
+    let cascades = shadows.cascades.clamp(1, MAX_CASCADES);
+    let (near, far) = (camera.near, shadows.distance.max(camera.near));
+
+    let forward = normalize(sub(camera.target, camera.position));
+    let right = normalize(cross(forward, camera.up));
+    let up = cross(right, forward);
+    let tan_half_fov = (camera.fov.to_radians() / 2.).tan();
+
+    let direction = normalize(direction);
+    let light_up = if direction.y.abs() > 0.99 {
+        Vec3::new(0., 0., 1.)
+    } else {
+        Vec3::new(0., 1., 0.)
+    };
+    let light_right = normalize(cross(direction, light_up));
+    let light_up = cross(light_right, direction);
+
+    let mut previous = near;
+    cascade_splits(near, far, cascades)
+        .map(|split| {
+            let corners: Vec<_> = [previous, split]
+                .into_iter()
+                .flat_map(|depth| {
+                    let (h, w) = (depth * tan_half_fov, depth * tan_half_fov * camera.aspect);
+                    let center = add(camera.position, mul(forward, depth));
+                    [(-1., -1.), (1., -1.), (-1., 1.), (1., 1.)]
+                        .map(|(x, y)| add(center, add(mul(right, w * x), mul(up, h * y))))
+                })
+                .collect();
+            previous = split;
+
+            let mut center = mul(
+                corners
+                    .iter()
+                    .fold(Vec3::new(0., 0., 0.), |sum, c| add(sum, *c)),
+                1. / corners.len() as f32,
+            );
+            let radius = corners
+                .iter()
+                .map(|c| length(sub(*c, center)))
+                .fold(0., f32::max);
+            // Fixed sizes keep the texel grid from changing between frames.
+            let radius = (radius * 16.).ceil() / 16.;
+
+            let texel = 2. * radius / resolution as f32;
+            let snap = |axis: Vec3| {
+                let offset = dot(center, axis);
+                (offset / texel).floor() * texel - offset
+            };
+            let (dx, dy) = (snap(light_right), snap(light_up));
+            center = add(center, add(mul(light_right, dx), mul(light_up, dy)));
+
+            // Casters up to `distance` towards the light still shadow the cascade.
+            let depth = 2. * radius + shadows.distance;
+            let eye = sub(center, mul(direction, radius + shadows.distance));
+            let view = lookat3(eye, center, light_up);
+            let projection = scale3(Vec3::new(1. / radius, 1. / radius, -2. / depth))
+                * translate3(Vec3::new(0., 0., depth / 2.));
+
+            ShadowView {
+                light_space: projection * view,
+                split,
+            }
+        })
+        .collect()
+}
+
+/// Far distance of each of the `cascades` between `near` and `far`, the last one is `far`.
+fn cascade_splits(near: f32, far: f32, cascades: u32) -> impl Iterator<Item = f32> {
+    (1..=cascades).map(move |i| {
+        let t = i as f32 / cascades as f32;
+        let uniform = near + (far - near) * t;
+        let logarithmic = near * (far / near).powf(t);
+        logarithmic * CASCADE_SPLIT_LAMBDA + uniform * (1. - CASCADE_SPLIT_LAMBDA)
+    })
+}
+
+/// Perspective view along the cone, reaching `far`.
+pub fn spot_view(position: Vec3, direction: Vec3, outer_angle: f32, far: f32) -> ShadowView {
+    let direction = normalize(direction);
+    let up = if direction.y.abs() > 0.99 {
+        Vec3::new(0., 0., 1.)
+    } else {
+        Vec3::new(0., 1., 0.)
+    };
+    let fov = (outer_angle * 2.).clamp(1., 179.);
+    let view = lookat3(position, add(position, direction), up);
+    let projection = perspective3(far.max(SPOT_NEAR_PLANE * 2.), SPOT_NEAR_PLANE, 1., fov);
+
+    ShadowView {
+        light_space: projection * view,
+        split: 0.,
+    }
+}
+
+/// Depth texture array the shadow pass renders every [`ShadowView`] into.
+#[derive(Debug)]
+pub struct ShadowMaps {
+    texture: Texture2DArray,
+    resolution: u32,
+    shader: Shader,
+    alpha_test_shader: Shader,
+    fbo: GLuint,
+}
+
+impl ShadowMaps {
+    /// Starts with a single 1x1 layer so the shadow sampler is always complete.
+    pub fn new(gl: &GL) -> Self {
+        let compile = |fragment: &str| {
+            ShaderSource::from_strings(VERTEX_SHADER.to_string(), fragment.to_string()).compile(gl)
+        };
+        let shader = compile(FRAGMENT_SHADER);
+        let alpha_test_shader = compile(ALPHA_TEST_FRAGMENT_SHADER);
+
+        let mut fbo = 0;
+        unsafe {
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+            gl::DrawBuffer(gl::NONE);
+            gl::ReadBuffer(gl::NONE);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+
+        Self {
+            texture: allocate(1, 1),
+            resolution: 1,
+            shader,
+            alpha_test_shader,
+            fbo,
+        }
+    }
+
+    pub fn texture(&self) -> &Texture2DArray {
+        &self.texture
+    }
+
+    /// Depth-only shader for solid casters.
+    pub fn shader(&self) -> &Shader {
+        &self.shader
+    }
+
+    /// Depth-only shader discarding fragments whose [`ALPHA_MAP`] alpha is below `alphaCutoff`.
+    pub fn alpha_test_shader(&self) -> &Shader {
+        &self.alpha_test_shader
+    }
+
+    /// Reallocates the maps at `resolution` if they're a different size or have fewer than `layers`.
+    /// Returns whether the texture was replaced.
+    pub fn reserve(&mut self, resolution: u32, layers: usize) -> bool {
+        let layers = layers.clamp(1, MAX_SHADOW_MAPS) as u32;
+        if self.resolution == resolution && self.texture.layers() >= layers {
+            return false;
+        }
+
+        unsafe { gl::DeleteTextures(1, &self.texture.id()) };
+        self.texture = allocate(resolution, layers);
+        self.resolution = resolution;
+        true
+    }
+
+    /// Renders `draw`'s geometry into one layer per view, `draw` uses [`Self::shader`]
+    /// and [`Self::alpha_test_shader`]. Leaves the shadow framebuffer bound
+    /// and either shader as the current program.
+    pub fn render<F>(&self, views: &[ShadowView], mut draw: F)
+    where
+        F: FnMut(),
+    {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::Viewport(0, 0, self.resolution as _, self.resolution as _);
+        }
+
+        views
+            .iter()
+            .take(self.texture.layers() as usize)
+            .enumerate()
+            .for_each(|(layer, view)| {
+                unsafe {
+                    gl::FramebufferTextureLayer(
+                        gl::FRAMEBUFFER,
+                        gl::DEPTH_ATTACHMENT,
+                        self.texture.id(),
+                        0,
+                        layer as _,
+                    );
+                    gl::Clear(gl::DEPTH_BUFFER_BIT);
+                }
+                [&self.shader, &self.alpha_test_shader]
+                    .into_iter()
+                    .for_each(|shader| {
+                        shader.set_uniform("lightSpace", Uniform::Matrix4(view.light_space))
+                    });
+                draw();
+            });
+    }
+}
+
+fn allocate(resolution: u32, layers: u32) -> Texture2DArray {
+    // Outside the maps everything is lit.
+    let parameters = TextureParameters {
+        mag_fiter: FilterOptions::Linear,
+        min_filter: FilterOptions::Linear,
+        ..TextureParameters::clamped_to_border(Vector4::new(1., 1., 1., 1.))
+    };
+    let texture = Texture2DArray::empty(
+        (resolution, resolution),
+        layers,
+        PixelFormat::DEPTH32F,
+        parameters.without_mipmaps(),
+    );
+
+    // Compare mode makes linear filtering return the fraction of lit texels.
+    unsafe {
+        gl::TexParameteri(
+            gl::TEXTURE_2D_ARRAY,
+            gl::TEXTURE_COMPARE_MODE,
+            gl::COMPARE_REF_TO_TEXTURE as _,
+        );
+        gl::TexParameteri(
+            gl::TEXTURE_2D_ARRAY,
+            gl::TEXTURE_COMPARE_FUNC,
+            gl::LEQUAL as _,
+        );
+    }
+
+    texture
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cascade_splits_increase_up_to_the_shadow_distance() {
+        for (near, far, cascades) in [(0.1, 50., 4), (0.5, 20., 3), (1., 1000., 2), (0.1, 10., 1)] {
+            let splits: Vec<_> = cascade_splits(near, far, cascades).collect();
+
+            assert_eq!(splits.len(), cascades as usize);
+            assert!(splits[0] > near);
+            assert!(splits.windows(2).all(|pair| pair[0] < pair[1]));
+            assert!((splits[splits.len() - 1] - far).abs() <= far * 1e-6);
+        }
+    }
+
+    #[test]
+    fn cascade_splits_favour_detail_near_the_camera() {
+        let splits: Vec<_> = cascade_splits(0.1, 100., 4).collect();
+        assert!(splits[0] < 25.);
+    }
+}
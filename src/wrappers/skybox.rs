@@ -0,0 +1,86 @@
+use engine_math::Vector2;
+
+use crate::object::components::Transform;
+
+use super::{
+    gl::GL,
+    mesh::{BoundStaticMesh, Mesh, Vertex},
+    shader::{Shader, ShaderSource},
+    textures::Texture,
+    types::{Uniform, Vec3, Vec4},
+    uniform_buffer::{CameraBlock, UniformBlock, CAMERA_BLOCK_BINDING},
+};
+
+const VERTEX_SHADER: &str = include_str!("../../shaders/skybox_vertex.glsl");
+const FRAGMENT_SHADER: &str = include_str!("../../shaders/skybox_fragment.glsl");
+
+/// Built-in pass drawing a cube map behind all opaque geometry.
+#[derive(Debug)]
+pub struct Skybox {
+    shader: Shader,
+    mesh: BoundStaticMesh,
+    cubemap: String,
+}
+
+impl Skybox {
+    pub fn new(gl: &GL, cubemap: String) -> Self {
+        let shader =
+            ShaderSource::from_strings(VERTEX_SHADER.to_string(), FRAGMENT_SHADER.to_string())
+                .compile(gl);
+        shader.bind_uniform_block(CameraBlock::NAME, CAMERA_BLOCK_BINDING);
+        let mesh = cube().create_static(&shader);
+
+        Self {
+            shader,
+            mesh,
+            cubemap,
+        }
+    }
+
+    pub fn cubemap(&self) -> &str {
+        &self.cubemap
+    }
+
+    pub fn set_cubemap(&mut self, cubemap: String) {
+        self.cubemap = cubemap;
+    }
+
+    /// Must run after opaque geometry: the sky sits on the far plane
+    /// and is only kept where nothing else was drawn.
+    pub fn draw(&self, cubemap: &dyn Texture) {
+        unsafe { gl::DepthFunc(gl::LEQUAL) };
+
+        cubemap.bind(0.into());
+        self.shader.set_uniform("skybox", Uniform::Int(0));
+        self.shader.draw(&Transform::default(), &self.mesh).unwrap();
+
+        unsafe { gl::DepthFunc(gl::LESS) };
+    }
+}
+
+pub(crate) fn cube() -> Mesh {
+    let vertices = (0..8)
+        .map(|i| Vertex {
+            pos: Vec3::new(
+                if i & 1 == 0 { -1. } else { 1. },
+                if i & 2 == 0 { -1. } else { 1. },
+                if i & 4 == 0 { -1. } else { 1. },
+            ),
+            tex: Vector2::new(0., 0.),
+            normal: Vec3::new(0., 0., 0.),
+            tangent: Vec4::new(0., 0., 0., 0.),
+        })
+        .collect();
+
+    // Wound to face inwards, the camera always sits inside the cube.
+    let indices = vec![
+        0, 1, 2, 1, 3, 2, // -Z
+        4, 6, 5, 5, 6, 7, // +Z
+        0, 2, 4, 2, 6, 4, // -X
+        1, 5, 3, 3, 5, 7, // +X
+        0, 4, 1, 1, 4, 5, // -Y
+        2, 3, 6, 3, 7, 6, // +Y
+    ];
+
+    Mesh::new(vertices, indices)
+}
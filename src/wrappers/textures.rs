@@ -1,46 +1,305 @@
-use std::path::Path;
+use std::{
+    fmt::{Debug, Display},
+    io,
+    path::Path,
+};
 
 use engine_math::Vector4;
-use gl::types::{GLfloat, GLuint};
-use image::{DynamicImage, GenericImageView};
+use gl::types::{GLenum, GLfloat, GLuint};
+use image::{ColorType, DynamicImage, GenericImageView, ImageBuffer, Rgb, Rgb32FImage};
 
 use super::{
+    gl::has_extension,
     shader::Shader,
-    types::{FilterOptions, TextureOptions, TextureUnit, Uniform},
+    types::{
+        unpack_alignment, ColorSpace, FilterOptions, PixelFormat, TextureOptions, TextureUnit,
+        Uniform,
+    },
 };
 
+const TEXTURE_MAX_ANISOTROPY_EXT: GLenum = 0x84FE;
+const MAX_TEXTURE_MAX_ANISOTROPY_EXT: GLenum = 0x84FF;
+
+/// GPU texture object that can be bound to a texture unit.
+pub trait Texture: Debug {
+    fn id(&self) -> GLuint;
+
+    fn target(&self) -> GLenum;
+
+    /// Lets updatable textures be reached through a `dyn Texture`.
+    fn as_texture_2d(&self) -> Option<&Texture2D> {
+        None
+    }
+
+    fn as_texture_2d_mut(&mut self) -> Option<&mut Texture2D> {
+        None
+    }
+
+    fn bind(&self, texture_unit: TextureUnit) {
+        unsafe {
+            gl::ActiveTexture(texture_unit.into());
+            gl::BindTexture(self.target(), self.id());
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Texture2D {
     id: GLuint,
+    size: (u32, u32),
+    /// `None` for textures holding compressed blocks, which can't be updated or read back.
+    format: Option<PixelFormat>,
+    mipmaps: bool,
+}
+
+impl Texture for Texture2D {
+    fn id(&self) -> GLuint {
+        self.id
+    }
+
+    fn target(&self) -> GLenum {
+        gl::TEXTURE_2D
+    }
+
+    fn as_texture_2d(&self) -> Option<&Texture2D> {
+        Some(self)
+    }
+
+    fn as_texture_2d_mut(&mut self) -> Option<&mut Texture2D> {
+        Some(self)
+    }
 }
 
 impl Texture2D {
-    pub fn load<P>(file_name: P, name: &str) -> BuilderTexture2D
-    where
-        P: AsRef<Path>,
-    {
-        let img = image::open(file_name).unwrap();
-        BuilderTexture2D {
-            img,
-            name: name.into(),
-            parameters: TextureParameters::default(),
+    pub(crate) fn from_id(
+        id: GLuint,
+        size: (u32, u32),
+        format: Option<PixelFormat>,
+        mipmaps: bool,
+    ) -> Self {
+        Self {
+            id,
+            size,
+            format,
+            mipmaps,
         }
     }
 
-    pub fn bind(&self, texture_unit: TextureUnit) {
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    pub fn format(&self) -> Option<PixelFormat> {
+        self.format
+    }
+
+    /// Overwrites the pixels starting at `(x, y)` with `img`, converted to the texture's format.
+    /// Mipmaps are regenerated if the texture has them.
+    pub fn update_region(&self, x: u32, y: u32, img: &DynamicImage) -> Result<(), Error> {
+        let format = self.updatable_format()?;
+        let (width, height) = img.dimensions();
+        let fits =
+            |start: u32, len: u32, size: u32| start.checked_add(len).is_some_and(|end| end <= size);
+        if !fits(x, width, self.size.0) || !fits(y, height, self.size.1) {
+            return Err(Error::OutOfBounds {
+                region: (x, y, width, height),
+                size: self.size,
+            });
+        }
+
+        let img = convert_image(img, format)
+            .ok_or_else(|| Error::UnsupportedFormat(format!("{format:?}")))?;
+        let row_bytes = width as usize * format.bytes_per_pixel();
+
         unsafe {
-            gl::ActiveTexture(texture_unit.into());
             gl::BindTexture(gl::TEXTURE_2D, self.id);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, unpack_alignment(row_bytes));
+            gl::TexSubImage2D(
+                gl::TEXTURE_2D,
+                0,
+                x as _,
+                y as _,
+                width as _,
+                height as _,
+                format.format,
+                format.data_type,
+                img.as_bytes().as_ptr().cast(),
+            );
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+
+            if self.mipmaps {
+                gl::GenerateMipmap(gl::TEXTURE_2D);
+            }
         }
+
+        Ok(())
+    }
+
+    /// Reallocates the texture, keeping the contents of the area both sizes share.
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<(), Error> {
+        let format = self.updatable_format()?;
+        let old = self.read_image()?;
+        self.reallocate((width, height), format);
+
+        let kept = old.crop_imm(0, 0, width.min(old.width()), height.min(old.height()));
+        self.update_region(0, 0, &kept)
+    }
+
+    /// Allocates level 0 with undefined contents, for render targets and textures filled later.
+    pub fn empty(size: (u32, u32), format: PixelFormat, parameters: TextureParameters) -> Self {
+        let mut id = 0;
+        unsafe {
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_2D, id);
+            parameters.apply(gl::TEXTURE_2D);
+            set_swizzle(gl::TEXTURE_2D, format);
+        }
+
+        let mut texture = Self::from_id(id, size, Some(format), parameters.generate_mipmaps);
+        texture.reallocate(size, format);
+        texture
+    }
+
+    /// Replaces level 0 with undefined contents of `size`.
+    pub(crate) fn reallocate(&mut self, (width, height): (u32, u32), format: PixelFormat) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                format.internal_format as _,
+                width as _,
+                height as _,
+                0,
+                format.format,
+                format.data_type,
+                std::ptr::null(),
+            );
+            if self.mipmaps {
+                gl::GenerateMipmap(gl::TEXTURE_2D);
+            }
+        }
+        self.size = (width, height);
+    }
+
+    /// Level 0 as tightly packed rows in the texture's format, top row first.
+    pub fn read_pixels(&self) -> Result<Vec<u8>, Error> {
+        let format = self.updatable_format()?;
+        let (width, height) = self.size;
+        let mut pixels = vec![0u8; width as usize * height as usize * format.bytes_per_pixel()];
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::GetTexImage(
+                gl::TEXTURE_2D,
+                0,
+                format.format,
+                format.data_type,
+                pixels.as_mut_ptr().cast(),
+            );
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 4);
+        }
+
+        Ok(pixels)
+    }
+
+    pub fn read_image(&self) -> Result<DynamicImage, Error> {
+        let format = self.updatable_format()?;
+        let (width, height) = self.size;
+        image_from_bytes(format, width, height, self.read_pixels()?)
+            .ok_or_else(|| Error::UnsupportedFormat(format!("{format:?}")))
+    }
+
+    fn updatable_format(&self) -> Result<PixelFormat, Error> {
+        self.format.ok_or_else(|| {
+            Error::UnsupportedFormat("compressed textures can't be updated or read back".into())
+        })
+    }
+
+    pub fn load<P>(file_name: P, name: &str) -> Result<BuilderTexture2D, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let img = image::open(file_name).map_err(Error::Image)?;
+        Ok(Self::from_image(img, name))
+    }
+
+    pub fn from_image(img: DynamicImage, name: &str) -> BuilderTexture2D {
+        BuilderTexture2D::new(img, name.into())
+    }
+
+    /// Tightly packed 8-bit pixels, rows going top to bottom.
+    /// Supports `L8`, `La8`, `Rgb8` and `Rgba8` data.
+    pub fn from_raw(
+        (width, height): (u32, u32),
+        color_type: ColorType,
+        data: Vec<u8>,
+        name: &str,
+    ) -> Result<BuilderTexture2D, Error> {
+        let expected = width as usize * height as usize * color_type.bytes_per_pixel() as usize;
+        let actual = data.len();
+        if actual != expected {
+            return Err(Error::DataLength { expected, actual });
+        }
+
+        let img = match color_type {
+            ColorType::L8 => {
+                ImageBuffer::from_raw(width, height, data).map(DynamicImage::ImageLuma8)
+            }
+            ColorType::La8 => {
+                ImageBuffer::from_raw(width, height, data).map(DynamicImage::ImageLumaA8)
+            }
+            ColorType::Rgb8 => {
+                ImageBuffer::from_raw(width, height, data).map(DynamicImage::ImageRgb8)
+            }
+            ColorType::Rgba8 => {
+                ImageBuffer::from_raw(width, height, data).map(DynamicImage::ImageRgba8)
+            }
+            other => return Err(Error::UnsupportedFormat(format!("{other:?}"))),
+        };
+
+        let img = img.ok_or(Error::DataLength { expected, actual })?;
+        Ok(Self::from_image(img, name))
     }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct TextureParameters {
     pub wrap_s: TextureOptions,
     pub wrap_t: TextureOptions,
+    /// Only used by cube maps and 3D textures.
+    pub wrap_r: TextureOptions,
+    /// Only the non-mipmap modes are valid for magnification,
+    /// mipmap modes fall back to their base filter.
     pub mag_fiter: FilterOptions,
     pub min_filter: FilterOptions,
+    pub color_space: ColorSpace,
+    pub generate_mipmaps: bool,
+    pub lod_bias: f32,
+    pub min_lod: f32,
+    pub max_lod: f32,
+    /// Requested anisotropy, `1.0` disables it. Clamped to what the driver
+    /// supports and ignored without `GL_EXT_texture_filter_anisotropic`.
+    pub anisotropy: f32,
+}
+
+impl Default for TextureParameters {
+    fn default() -> Self {
+        Self {
+            wrap_s: TextureOptions::default(),
+            wrap_t: TextureOptions::default(),
+            wrap_r: TextureOptions::default(),
+            mag_fiter: FilterOptions::default(),
+            min_filter: FilterOptions::default(),
+            color_space: ColorSpace::default(),
+            generate_mipmaps: true,
+            lod_bias: 0.,
+            min_lod: -1000.,
+            max_lod: 1000.,
+            anisotropy: 1.,
+        }
+    }
 }
 
 impl TextureParameters {
@@ -48,6 +307,7 @@ impl TextureParameters {
         Self {
             wrap_s: TextureOptions::ClampToEdge,
             wrap_t: TextureOptions::ClampToEdge,
+            wrap_r: TextureOptions::ClampToEdge,
             ..Default::default()
         }
     }
@@ -56,6 +316,7 @@ impl TextureParameters {
         Self {
             wrap_s: TextureOptions::ClampToBorder(col),
             wrap_t: TextureOptions::ClampToBorder(col),
+            wrap_r: TextureOptions::ClampToBorder(col),
             ..Default::default()
         }
     }
@@ -75,6 +336,113 @@ impl TextureParameters {
             ..Default::default()
         }
     }
+
+    /// Trilinear filtering with `anisotropy` samples where supported.
+    pub fn anisotropic(anisotropy: f32) -> Self {
+        Self {
+            mag_fiter: FilterOptions::Linear,
+            min_filter: FilterOptions::LinearMipmapLinear,
+            anisotropy,
+            ..Default::default()
+        }
+    }
+
+    /// Also drops the mip selection from the minifying filter, as there is a single level to sample.
+    pub fn without_mipmaps(self) -> Self {
+        let min_filter = match self.min_filter.uses_mipmaps() {
+            true => self.min_filter.base_filter(),
+            false => self.min_filter,
+        };
+        Self {
+            generate_mipmaps: false,
+            min_filter,
+            ..self
+        }
+    }
+
+    /// Edge-clamped, linearly filtered, as seams show up with any other wrapping.
+    pub fn cube_map() -> Self {
+        Self {
+            wrap_s: TextureOptions::ClampToEdge,
+            wrap_t: TextureOptions::ClampToEdge,
+            wrap_r: TextureOptions::ClampToEdge,
+            mag_fiter: FilterOptions::Linear,
+            min_filter: FilterOptions::Linear,
+            generate_mipmaps: false,
+            ..Default::default()
+        }
+    }
+
+    /// Parameters for normal maps and other non-color data.
+    pub fn data() -> Self {
+        Self {
+            color_space: ColorSpace::Linear,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the parameters on the texture currently bound to `target`.
+    pub fn apply(&self, target: GLenum) {
+        unsafe {
+            gl::TexParameteri(target, gl::TEXTURE_WRAP_S, self.wrap_s.into());
+            if let TextureOptions::ClampToBorder(col) = self.wrap_s {
+                gl::TexParameterfv(
+                    target,
+                    gl::TEXTURE_BORDER_COLOR,
+                    &col as *const Vector4 as *const GLfloat,
+                );
+            }
+
+            gl::TexParameteri(target, gl::TEXTURE_WRAP_T, self.wrap_t.into());
+            if let TextureOptions::ClampToBorder(col) = self.wrap_t {
+                gl::TexParameterfv(
+                    target,
+                    gl::TEXTURE_BORDER_COLOR,
+                    &col as *const Vector4 as *const GLfloat,
+                );
+            }
+
+            if matches!(target, gl::TEXTURE_3D | gl::TEXTURE_CUBE_MAP) {
+                gl::TexParameteri(target, gl::TEXTURE_WRAP_R, self.wrap_r.into());
+                if let TextureOptions::ClampToBorder(col) = self.wrap_r {
+                    gl::TexParameterfv(
+                        target,
+                        gl::TEXTURE_BORDER_COLOR,
+                        &col as *const Vector4 as *const GLfloat,
+                    );
+                }
+            }
+
+            gl::TexParameteri(target, gl::TEXTURE_MIN_FILTER, self.min_filter.into());
+            gl::TexParameteri(
+                target,
+                gl::TEXTURE_MAG_FILTER,
+                self.mag_fiter.base_filter().into(),
+            );
+
+            gl::TexParameterf(target, gl::TEXTURE_LOD_BIAS, self.lod_bias);
+            gl::TexParameterf(target, gl::TEXTURE_MIN_LOD, self.min_lod);
+            gl::TexParameterf(target, gl::TEXTURE_MAX_LOD, self.max_lod);
+
+            // Without a mip chain the texture is only complete if sampling stops at level 0.
+            if !self.generate_mipmaps {
+                gl::TexParameteri(target, gl::TEXTURE_MAX_LEVEL, 0);
+            }
+
+            if self.anisotropy > 1.
+                && (has_extension("GL_EXT_texture_filter_anisotropic")
+                    || has_extension("GL_ARB_texture_filter_anisotropic"))
+            {
+                let mut max_anisotropy = 1.;
+                gl::GetFloatv(MAX_TEXTURE_MAX_ANISOTROPY_EXT, &mut max_anisotropy);
+                gl::TexParameterf(
+                    target,
+                    TEXTURE_MAX_ANISOTROPY_EXT,
+                    self.anisotropy.min(max_anisotropy),
+                );
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -85,6 +453,26 @@ pub struct BuilderTexture2D {
 }
 
 impl BuilderTexture2D {
+    pub(crate) fn new(img: DynamicImage, name: String) -> Self {
+        Self {
+            img,
+            name,
+            parameters: TextureParameters::default(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn image(&self) -> &DynamicImage {
+        &self.img
+    }
+
+    pub fn into_image(self) -> DynamicImage {
+        self.img
+    }
+
     pub fn flipv(mut self) -> Self {
         self.img = self.img.flipv();
         self
@@ -100,63 +488,712 @@ impl BuilderTexture2D {
         self
     }
 
+    pub fn set_color_space(mut self, color_space: ColorSpace) -> Self {
+        self.parameters.color_space = color_space;
+        self
+    }
+
     pub fn process(self, shader: &Shader) -> Texture2D {
         let Self {
             img,
             name,
             parameters,
         } = self;
+        let size = img.dimensions();
         unsafe {
-            let (width, height) = img.dimensions();
             let mut id = 0;
             gl::GenTextures(1, &mut id);
             gl::BindTexture(gl::TEXTURE_2D, id);
 
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, parameters.wrap_s.into());
-            if let TextureOptions::ClampToBorder(col) = parameters.wrap_s {
-                gl::TexParameterfv(
-                    gl::TEXTURE_2D,
-                    gl::TEXTURE_BORDER_COLOR,
-                    &col as *const Vector4 as *const GLfloat,
-                );
-            }
+            parameters.apply(gl::TEXTURE_2D);
 
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, parameters.wrap_t.into());
-            if let TextureOptions::ClampToBorder(col) = parameters.wrap_t {
-                gl::TexParameterfv(
-                    gl::TEXTURE_2D,
-                    gl::TEXTURE_BORDER_COLOR,
-                    &col as *const Vector4 as *const GLfloat,
-                );
+            let format = upload_image(gl::TEXTURE_2D, img, parameters.color_space);
+            set_swizzle(gl::TEXTURE_2D, format);
+
+            if parameters.generate_mipmaps {
+                gl::GenerateMipmap(gl::TEXTURE_2D);
             }
 
+            shader.set_uniform(&name, Uniform::Int(0));
+
+            Texture2D::from_id(id, size, Some(format), parameters.generate_mipmaps)
+        }
+    }
+}
+
+/// Picks the upload format for `img`, converting to RGBA8 when there's no direct match.
+fn upload_format(img: DynamicImage, color_space: ColorSpace) -> (DynamicImage, PixelFormat) {
+    let (img, format) = match PixelFormat::from_color_type(img.color()) {
+        Some(format) => (img, format),
+        None => {
+            let img = DynamicImage::ImageRgba8(img.to_rgba8());
+            let format = PixelFormat::from_color_type(img.color()).unwrap();
+            (img, format)
+        }
+    };
+
+    (img, format.with_color_space(color_space))
+}
+
+/// Uploads `img` as level 0 of `target`, which may be a single cube map face.
+unsafe fn upload_image(target: GLenum, img: DynamicImage, color_space: ColorSpace) -> PixelFormat {
+    let (img, format) = upload_format(img, color_space);
+    let (width, height) = img.dimensions();
+
+    let row_bytes = width as usize * img.color().bytes_per_pixel() as usize;
+    gl::PixelStorei(gl::UNPACK_ALIGNMENT, unpack_alignment(row_bytes));
+
+    gl::TexImage2D(
+        target,
+        0,
+        format.internal_format as _,
+        width as _,
+        height as _,
+        0,
+        format.format,
+        format.data_type,
+        img.as_bytes().as_ptr().cast(),
+    );
+    gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+
+    format
+}
+
+/// Converts `img` to the client layout of `format`, `None` for layouts images can't hold.
+fn convert_image(img: &DynamicImage, format: PixelFormat) -> Option<DynamicImage> {
+    let img = match (format.format, format.data_type) {
+        (gl::RED, gl::UNSIGNED_BYTE) => DynamicImage::ImageLuma8(img.to_luma8()),
+        (gl::RG, gl::UNSIGNED_BYTE) => DynamicImage::ImageLumaA8(img.to_luma_alpha8()),
+        (gl::RGB, gl::UNSIGNED_BYTE) => DynamicImage::ImageRgb8(img.to_rgb8()),
+        (gl::RGBA, gl::UNSIGNED_BYTE) => DynamicImage::ImageRgba8(img.to_rgba8()),
+        (gl::RED, gl::UNSIGNED_SHORT) => DynamicImage::ImageLuma16(img.to_luma16()),
+        (gl::RG, gl::UNSIGNED_SHORT) => DynamicImage::ImageLumaA16(img.to_luma_alpha16()),
+        (gl::RGB, gl::UNSIGNED_SHORT) => DynamicImage::ImageRgb16(img.to_rgb16()),
+        (gl::RGBA, gl::UNSIGNED_SHORT) => DynamicImage::ImageRgba16(img.to_rgba16()),
+        (gl::RGB, gl::FLOAT) => DynamicImage::ImageRgb32F(img.to_rgb32f()),
+        (gl::RGBA, gl::FLOAT) => DynamicImage::ImageRgba32F(img.to_rgba32f()),
+        _ => return None,
+    };
+
+    Some(img)
+}
+
+/// Inverse of [`convert_image`] for pixels read back from the GPU.
+fn image_from_bytes(
+    format: PixelFormat,
+    width: u32,
+    height: u32,
+    bytes: Vec<u8>,
+) -> Option<DynamicImage> {
+    fn words<T, const N: usize>(bytes: &[u8], from: fn([u8; N]) -> T) -> Vec<T> {
+        bytes
+            .chunks_exact(N)
+            .map(|c| from(c.try_into().unwrap()))
+            .collect()
+    }
+
+    let (w, h) = (width, height);
+    match (format.format, format.data_type) {
+        (gl::RED, gl::UNSIGNED_BYTE) => {
+            ImageBuffer::from_raw(w, h, bytes).map(DynamicImage::ImageLuma8)
+        }
+        (gl::RG, gl::UNSIGNED_BYTE) => {
+            ImageBuffer::from_raw(w, h, bytes).map(DynamicImage::ImageLumaA8)
+        }
+        (gl::RGB, gl::UNSIGNED_BYTE) => {
+            ImageBuffer::from_raw(w, h, bytes).map(DynamicImage::ImageRgb8)
+        }
+        (gl::RGBA, gl::UNSIGNED_BYTE) => {
+            ImageBuffer::from_raw(w, h, bytes).map(DynamicImage::ImageRgba8)
+        }
+        (gl::RED, gl::UNSIGNED_SHORT) => {
+            ImageBuffer::from_raw(w, h, words(&bytes, u16::from_ne_bytes))
+                .map(DynamicImage::ImageLuma16)
+        }
+        (gl::RG, gl::UNSIGNED_SHORT) => {
+            ImageBuffer::from_raw(w, h, words(&bytes, u16::from_ne_bytes))
+                .map(DynamicImage::ImageLumaA16)
+        }
+        (gl::RGB, gl::UNSIGNED_SHORT) => {
+            ImageBuffer::from_raw(w, h, words(&bytes, u16::from_ne_bytes))
+                .map(DynamicImage::ImageRgb16)
+        }
+        (gl::RGBA, gl::UNSIGNED_SHORT) => {
+            ImageBuffer::from_raw(w, h, words(&bytes, u16::from_ne_bytes))
+                .map(DynamicImage::ImageRgba16)
+        }
+        (gl::RGB, gl::FLOAT) => ImageBuffer::from_raw(w, h, words(&bytes, f32::from_ne_bytes))
+            .map(DynamicImage::ImageRgb32F),
+        (gl::RGBA, gl::FLOAT) => ImageBuffer::from_raw(w, h, words(&bytes, f32::from_ne_bytes))
+            .map(DynamicImage::ImageRgba32F),
+        _ => None,
+    }
+}
+
+unsafe fn set_swizzle(target: GLenum, format: PixelFormat) {
+    if let Some(swizzle) = format.swizzle {
+        gl::TexParameteriv(target, gl::TEXTURE_SWIZZLE_RGBA, swizzle.as_ptr().cast());
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TextureCube {
+    id: GLuint,
+}
+
+impl Texture for TextureCube {
+    fn id(&self) -> GLuint {
+        self.id
+    }
+
+    fn target(&self) -> GLenum {
+        gl::TEXTURE_CUBE_MAP
+    }
+}
+
+impl TextureCube {
+    /// Faces in `+X, -X, +Y, -Y, +Z, -Z` order. They must be square and all the same size,
+    /// or the cube map would be incomplete.
+    pub fn from_images(faces: [DynamicImage; 6]) -> Result<BuilderTextureCube, Error> {
+        let size = faces[0].dimensions();
+        if let Some((face, img)) = faces
+            .iter()
+            .enumerate()
+            .find(|(_, img)| img.width() != img.height() || img.dimensions() != size)
+        {
+            let (width, height) = img.dimensions();
+            return Err(Error::Dimensions(format!(
+                "cube map face {face} is {width}x{height}, faces must be square and match the \
+                 {}x{} first face",
+                size.0, size.1
+            )));
+        }
+
+        Ok(BuilderTextureCube {
+            faces,
+            parameters: TextureParameters::cube_map(),
+        })
+    }
+
+    /// Allocates `levels` mip levels of every face with undefined contents, for rendering into.
+    pub fn empty(
+        face_size: u32,
+        format: PixelFormat,
+        levels: u32,
+        parameters: TextureParameters,
+    ) -> Self {
+        unsafe {
+            let mut id = 0;
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, id);
+
+            parameters.apply(gl::TEXTURE_CUBE_MAP);
+
+            for level in 0..levels {
+                let size = (face_size >> level).max(1);
+                for face in 0..6 {
+                    gl::TexImage2D(
+                        gl::TEXTURE_CUBE_MAP_POSITIVE_X + face,
+                        level as _,
+                        format.internal_format as _,
+                        size as _,
+                        size as _,
+                        0,
+                        format.format,
+                        format.data_type,
+                        std::ptr::null(),
+                    );
+                }
+            }
             gl::TexParameteri(
-                gl::TEXTURE_2D,
-                gl::TEXTURE_MIN_FILTER,
-                parameters.min_filter.into(),
+                gl::TEXTURE_CUBE_MAP,
+                gl::TEXTURE_MAX_LEVEL,
+                levels as i32 - 1,
             );
-            gl::TexParameteri(
-                gl::TEXTURE_2D,
-                gl::TEXTURE_MAG_FILTER,
-                parameters.mag_fiter.into(),
+
+            TextureCube { id }
+        }
+    }
+
+    /// Loads the six faces in `+X, -X, +Y, -Y, +Z, -Z` order.
+    pub fn load<P>(faces: [P; 6]) -> Result<BuilderTextureCube, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let [px, nx, py, ny, pz, nz] = faces.map(image::open);
+        Self::from_images([px?, nx?, py?, ny?, pz?, nz?])
+    }
+
+    /// Projects an equirectangular panorama, usually an HDR, onto faces of `face_size` pixels.
+    pub fn load_equirectangular<P>(
+        file_name: P,
+        face_size: u32,
+    ) -> Result<BuilderTextureCube, Error>
+    where
+        P: AsRef<Path>,
+    {
+        if face_size == 0 {
+            return Err(Error::Dimensions("cube map faces can't be empty".into()));
+        }
+        let panorama = image::open(file_name)?.to_rgb32f();
+        if panorama.width() == 0 || panorama.height() == 0 {
+            return Err(Error::Dimensions("the panorama is empty".into()));
+        }
+        let faces = [0, 1, 2, 3, 4, 5].map(|face| {
+            let face = ImageBuffer::from_fn(face_size, face_size, |x, y| {
+                let s = 2. * (x as f32 + 0.5) / face_size as f32 - 1.;
+                let t = 2. * (y as f32 + 0.5) / face_size as f32 - 1.;
+                sample_equirectangular(&panorama, cube_face_direction(face, s, t))
+            });
+            DynamicImage::ImageRgb32F(face)
+        });
+
+        Self::from_images(faces)
+    }
+}
+
+/// Direction through texel `(s, t)` of `face`, per the cube map face selection table.
+fn cube_face_direction(face: usize, s: f32, t: f32) -> [f32; 3] {
+    match face {
+        0 => [1., -t, -s],
+        1 => [-1., -t, s],
+        2 => [s, 1., t],
+        3 => [s, -1., -t],
+        4 => [s, -t, 1.],
+        _ => [-s, -t, -1.],
+    }
+}
+
+fn sample_equirectangular(panorama: &Rgb32FImage, [x, y, z]: [f32; 3]) -> Rgb<f32> {
+    use std::f32::consts::PI;
+
+    let length = (x * x + y * y + z * z).sqrt();
+    let u = 0.5 + z.atan2(x) / (2. * PI);
+    let v = (y / length).clamp(-1., 1.).acos() / PI;
+
+    let (width, height) = panorama.dimensions();
+    let px = (u * width as f32 - 0.5).rem_euclid(width as f32);
+    let py = (v * height as f32 - 0.5).clamp(0., (height - 1) as f32);
+    let (x0, y0) = (px.floor() as u32, py.floor() as u32);
+    let (x1, y1) = ((x0 + 1) % width, (y0 + 1).min(height - 1));
+    let (fx, fy) = (px.fract(), py.fract());
+
+    let lerp = |a: &Rgb<f32>, b: &Rgb<f32>, f: f32| -> [f32; 3] {
+        [0, 1, 2].map(|c| a.0[c] + (b.0[c] - a.0[c]) * f)
+    };
+    let top = lerp(panorama.get_pixel(x0, y0), panorama.get_pixel(x1, y0), fx);
+    let bottom = lerp(panorama.get_pixel(x0, y1), panorama.get_pixel(x1, y1), fx);
+
+    Rgb([0, 1, 2].map(|c| top[c] + (bottom[c] - top[c]) * fy))
+}
+
+#[derive(Debug, Clone)]
+pub struct BuilderTextureCube {
+    faces: [DynamicImage; 6],
+    parameters: TextureParameters,
+}
+
+impl BuilderTextureCube {
+    pub fn set_parameters(mut self, parameters: TextureParameters) -> Self {
+        self.parameters = parameters;
+        self
+    }
+
+    pub fn set_color_space(mut self, color_space: ColorSpace) -> Self {
+        self.parameters.color_space = color_space;
+        self
+    }
+
+    pub fn process(self) -> TextureCube {
+        let Self { faces, parameters } = self;
+
+        unsafe {
+            let mut id = 0;
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, id);
+
+            parameters.apply(gl::TEXTURE_CUBE_MAP);
+
+            let mut format = None;
+            for (face, img) in faces.into_iter().enumerate() {
+                let target = gl::TEXTURE_CUBE_MAP_POSITIVE_X + face as GLenum;
+                format = Some(upload_image(target, img, parameters.color_space));
+            }
+            set_swizzle(gl::TEXTURE_CUBE_MAP, format.unwrap());
+
+            if parameters.generate_mipmaps {
+                gl::GenerateMipmap(gl::TEXTURE_CUBE_MAP);
+            }
+
+            TextureCube { id }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Texture2DArray {
+    id: GLuint,
+    layers: u32,
+}
+
+impl Texture for Texture2DArray {
+    fn id(&self) -> GLuint {
+        self.id
+    }
+
+    fn target(&self) -> GLenum {
+        gl::TEXTURE_2D_ARRAY
+    }
+}
+
+impl Texture2DArray {
+    /// Every image becomes one layer, in order. All images must have the same size.
+    pub fn load<P>(file_names: &[P]) -> Result<BuilderTexture2DArray, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let layers = file_names
+            .iter()
+            .map(image::open)
+            .collect::<Result<_, _>>()?;
+        Self::from_images(layers)
+    }
+
+    /// Needs at least one layer, all of the same size.
+    pub fn from_images(layers: Vec<DynamicImage>) -> Result<BuilderTexture2DArray, Error> {
+        let Some(first) = layers.first() else {
+            return Err(Error::Dimensions(
+                "a texture array needs at least one layer".into(),
+            ));
+        };
+
+        let (width, height) = first.dimensions();
+        if let Some(img) = layers
+            .iter()
+            .find(|img| img.dimensions() != (width, height))
+        {
+            let (w, h) = img.dimensions();
+            return Err(Error::Dimensions(format!(
+                "texture array layers must all be {width}x{height}, got {w}x{h}"
+            )));
+        }
+
+        Ok(BuilderTexture2DArray {
+            layers,
+            parameters: TextureParameters::default(),
+        })
+    }
+
+    /// Splits a sprite sheet of `columns` by `rows` equally sized frames into layers,
+    /// row by row starting at the top left.
+    pub fn from_sprite_sheet<P>(
+        file_name: P,
+        columns: u32,
+        rows: u32,
+    ) -> Result<BuilderTexture2DArray, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let sheet = image::open(file_name)?;
+        let (width, height) = match (
+            sheet.width().checked_div(columns),
+            sheet.height().checked_div(rows),
+        ) {
+            (Some(width), Some(height)) if width > 0 && height > 0 => (width, height),
+            _ => {
+                return Err(Error::Dimensions(format!(
+                    "a {}x{} sheet can't be split into {columns}x{rows} frames",
+                    sheet.width(),
+                    sheet.height()
+                )))
+            }
+        };
+        let layers = (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (column, row)))
+            .map(|(column, row)| sheet.crop_imm(column * width, row * height, width, height))
+            .collect();
+        Self::from_images(layers)
+    }
+
+    pub fn layers(&self) -> u32 {
+        self.layers
+    }
+
+    /// Allocates `layers` layers with undefined contents, for rendering into.
+    pub fn empty(
+        (width, height): (u32, u32),
+        layers: u32,
+        format: PixelFormat,
+        parameters: TextureParameters,
+    ) -> Self {
+        unsafe {
+            let mut id = 0;
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_2D_ARRAY, id);
+
+            parameters.apply(gl::TEXTURE_2D_ARRAY);
+
+            gl::TexImage3D(
+                gl::TEXTURE_2D_ARRAY,
+                0,
+                format.internal_format as _,
+                width as _,
+                height as _,
+                layers as _,
+                0,
+                format.format,
+                format.data_type,
+                std::ptr::null(),
             );
 
-            gl::TexImage2D(
-                gl::TEXTURE_2D,
+            Texture2DArray { id, layers }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BuilderTexture2DArray {
+    layers: Vec<DynamicImage>,
+    parameters: TextureParameters,
+}
+
+impl BuilderTexture2DArray {
+    pub fn flipv(mut self) -> Self {
+        self.layers = self.layers.into_iter().map(|img| img.flipv()).collect();
+        self
+    }
+
+    pub fn set_parameters(mut self, parameters: TextureParameters) -> Self {
+        self.parameters = parameters;
+        self
+    }
+
+    pub fn set_color_space(mut self, color_space: ColorSpace) -> Self {
+        self.parameters.color_space = color_space;
+        self
+    }
+
+    pub fn process(self) -> Texture2DArray {
+        let Self {
+            mut layers,
+            parameters,
+        } = self;
+
+        // Layer count and sizes were checked by `Texture2DArray::from_images`.
+        let (width, height) = layers[0].dimensions();
+
+        let color = layers[0].color();
+        if layers.iter().any(|img| img.color() != color) {
+            layers = layers
+                .into_iter()
+                .map(|img| DynamicImage::ImageRgba8(img.to_rgba8()))
+                .collect();
+        }
+
+        let count = layers.len() as u32;
+        let layers: Vec<_> = layers
+            .into_iter()
+            .map(|img| upload_format(img, parameters.color_space))
+            .collect();
+        let format = layers[0].1;
+
+        unsafe {
+            let mut id = 0;
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_2D_ARRAY, id);
+
+            parameters.apply(gl::TEXTURE_2D_ARRAY);
+            set_swizzle(gl::TEXTURE_2D_ARRAY, format);
+
+            gl::TexImage3D(
+                gl::TEXTURE_2D_ARRAY,
                 0,
-                gl::RGB as _,
+                format.internal_format as _,
                 width as _,
                 height as _,
+                count as _,
                 0,
-                gl::RGB as _,
-                gl::UNSIGNED_BYTE,
-                img.as_bytes().as_ptr().cast(),
+                format.format,
+                format.data_type,
+                std::ptr::null(),
             );
-            gl::GenerateMipmap(gl::TEXTURE_2D);
 
-            shader.set_uniform(&name, Uniform::Int(0));
+            let row_bytes = width as usize * format.bytes_per_pixel();
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, unpack_alignment(row_bytes));
+            layers.iter().enumerate().for_each(|(layer, (img, _))| {
+                gl::TexSubImage3D(
+                    gl::TEXTURE_2D_ARRAY,
+                    0,
+                    0,
+                    0,
+                    layer as _,
+                    width as _,
+                    height as _,
+                    1,
+                    format.format,
+                    format.data_type,
+                    img.as_bytes().as_ptr().cast(),
+                );
+            });
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+
+            if parameters.generate_mipmaps {
+                gl::GenerateMipmap(gl::TEXTURE_2D_ARRAY);
+            }
+
+            Texture2DArray { id, layers: count }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Texture3D {
+    id: GLuint,
+}
+
+impl Texture for Texture3D {
+    fn id(&self) -> GLuint {
+        self.id
+    }
+
+    fn target(&self) -> GLenum {
+        gl::TEXTURE_3D
+    }
+}
 
-            Texture2D { id }
+impl Texture3D {
+    /// Volume from tightly packed voxels, x fastest then y then z, laid out as `format` describes.
+    pub fn from_raw(
+        (width, height, depth): (u32, u32, u32),
+        format: PixelFormat,
+        data: Vec<u8>,
+    ) -> Result<BuilderTexture3D, Error> {
+        let expected = [width, height, depth]
+            .into_iter()
+            .try_fold(format.bytes_per_pixel(), |size, n| {
+                size.checked_mul(n as usize)
+            })
+            .ok_or_else(|| {
+                Error::Dimensions(format!("a {width}x{height}x{depth} volume is too large"))
+            })?;
+        if data.len() != expected {
+            return Err(Error::DataLength {
+                expected,
+                actual: data.len(),
+            });
         }
+
+        Ok(BuilderTexture3D {
+            data,
+            dimensions: (width, height, depth),
+            format,
+            parameters: TextureParameters {
+                color_space: ColorSpace::Linear,
+                ..Default::default()
+            },
+        })
     }
 }
+
+#[derive(Debug, Clone)]
+pub struct BuilderTexture3D {
+    data: Vec<u8>,
+    dimensions: (u32, u32, u32),
+    format: PixelFormat,
+    parameters: TextureParameters,
+}
+
+impl BuilderTexture3D {
+    pub fn set_parameters(mut self, parameters: TextureParameters) -> Self {
+        self.parameters = parameters;
+        self
+    }
+
+    pub fn process(self) -> Texture3D {
+        let Self {
+            data,
+            dimensions: (width, height, depth),
+            format,
+            parameters,
+        } = self;
+        let format = format.with_color_space(parameters.color_space);
+
+        unsafe {
+            let mut id = 0;
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_3D, id);
+
+            parameters.apply(gl::TEXTURE_3D);
+            set_swizzle(gl::TEXTURE_3D, format);
+
+            let row_bytes = width as usize * format.bytes_per_pixel();
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, unpack_alignment(row_bytes));
+            gl::TexImage3D(
+                gl::TEXTURE_3D,
+                0,
+                format.internal_format as _,
+                width as _,
+                height as _,
+                depth as _,
+                0,
+                format.format,
+                format.data_type,
+                data.as_ptr().cast(),
+            );
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+
+            if parameters.generate_mipmaps {
+                gl::GenerateMipmap(gl::TEXTURE_3D);
+            }
+
+            Texture3D { id }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Container(String),
+    UnsupportedFormat(String),
+    Image(image::ImageError),
+    DataLength {
+        expected: usize,
+        actual: usize,
+    },
+    OutOfBounds {
+        region: (u32, u32, u32, u32),
+        size: (u32, u32),
+    },
+    Dimensions(String),
+}
+
+impl From<image::ImageError> for Error {
+    fn from(value: image::ImageError) -> Self {
+        Error::Image(value)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{e}"),
+            Error::Container(s) => write!(f, "Malformed texture container: {s}"),
+            Error::UnsupportedFormat(s) => write!(f, "Unsupported texture format: {s}"),
+            Error::Image(e) => write!(f, "{e}"),
+            Error::DataLength { expected, actual } => {
+                write!(f, "Expected {expected} bytes of pixel data, got {actual}")
+            }
+            Error::OutOfBounds {
+                region: (x, y, w, h),
+                size: (width, height),
+            } => write!(
+                f,
+                "Region {w}x{h} at ({x}, {y}) doesn't fit in a {width}x{height} texture"
+            ),
+            Error::Dimensions(s) => write!(f, "Invalid texture dimensions: {s}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -1,4 +1,8 @@
-use std::fmt::Display;
+use std::{
+    fmt::Display,
+    ops::BitOr,
+    sync::atomic::{AtomicU32, Ordering},
+};
 
 use engine_math::{Matrix4, Vector2, Vector3, Vector4};
 use gl::types::GLuint;
@@ -26,28 +30,52 @@ pub enum InnerEvent {
     EventsClear,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum TextureUnit {
-    Texture0 = gl::TEXTURE0 as isize,
-    Texture1 = gl::TEXTURE1 as isize,
+/// Texture image unit, `TextureUnit(n)` maps to `GL_TEXTURE0 + n`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureUnit(pub u32);
+
+static MAX_TEXTURE_UNITS: AtomicU32 = AtomicU32::new(0);
+
+impl TextureUnit {
+    /// Number of units a fragment shader can sample from, as queried by [`GL::init`](super::gl::GL::init).
+    pub fn max_units() -> u32 {
+        MAX_TEXTURE_UNITS.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn query_max_units() {
+        let mut units = 0;
+        unsafe { gl::GetIntegerv(gl::MAX_TEXTURE_IMAGE_UNITS, &mut units) };
+        MAX_TEXTURE_UNITS.store(units as _, Ordering::Relaxed);
+    }
+
+    /// The samplers that get a unit when bound in order, logging the ones past [`Self::max_units`].
+    pub(crate) fn limit<'a, T, I>(textures: I) -> Vec<(&'a str, T)>
+    where
+        I: IntoIterator<Item = (&'a str, T)>,
+    {
+        let max_units = Self::max_units() as usize;
+        let mut textures = textures.into_iter();
+        let bound = textures.by_ref().take(max_units).collect();
+
+        let skipped: Vec<_> = textures.map(|(sampler, _)| sampler).collect();
+        if !skipped.is_empty() {
+            eprintln!(
+                "Only {max_units} texture units are available, skipped the samplers {skipped:?}"
+            );
+        }
+        bound
+    }
 }
 
 impl From<TextureUnit> for u32 {
     fn from(value: TextureUnit) -> Self {
-        match value {
-            TextureUnit::Texture0 => gl::TEXTURE0,
-            TextureUnit::Texture1 => gl::TEXTURE1,
-        }
+        gl::TEXTURE0 + value.0
     }
 }
 
 impl From<usize> for TextureUnit {
     fn from(value: usize) -> Self {
-        match value {
-            0 => TextureUnit::Texture0,
-            1 => TextureUnit::Texture1,
-            _ => todo!(),
-        }
+        TextureUnit(value as _)
     }
 }
 
@@ -90,6 +118,26 @@ impl Default for TextureOptions {
 pub enum FilterOptions {
     Nearest = gl::NEAREST as _,
     Linear = gl::LINEAR as _,
+    NearestMipmapNearest = gl::NEAREST_MIPMAP_NEAREST as _,
+    LinearMipmapNearest = gl::LINEAR_MIPMAP_NEAREST as _,
+    NearestMipmapLinear = gl::NEAREST_MIPMAP_LINEAR as _,
+    LinearMipmapLinear = gl::LINEAR_MIPMAP_LINEAR as _,
+}
+
+impl FilterOptions {
+    pub fn uses_mipmaps(&self) -> bool {
+        !matches!(self, FilterOptions::Nearest | FilterOptions::Linear)
+    }
+
+    /// Filter used within a single mip level.
+    pub fn base_filter(&self) -> Self {
+        match self {
+            FilterOptions::Nearest
+            | FilterOptions::NearestMipmapNearest
+            | FilterOptions::NearestMipmapLinear => FilterOptions::Nearest,
+            _ => FilterOptions::Linear,
+        }
+    }
 }
 
 impl From<FilterOptions> for i32 {
@@ -114,10 +162,14 @@ pub enum ProgramStatus {
     LinkStatus = gl::LINK_STATUS as isize,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ShaderType {
     VertexShader = gl::VERTEX_SHADER as isize,
     FragmentShader = gl::FRAGMENT_SHADER as isize,
+    GeometryShader = gl::GEOMETRY_SHADER as isize,
+    TessControlShader = gl::TESS_CONTROL_SHADER as isize,
+    TessEvaluationShader = gl::TESS_EVALUATION_SHADER as isize,
+    ComputeShader = gl::COMPUTE_SHADER as isize,
 }
 
 impl Display for ShaderType {
@@ -128,6 +180,10 @@ impl Display for ShaderType {
             match self {
                 ShaderType::VertexShader => "Vertex Shader",
                 ShaderType::FragmentShader => "Fragment Shader",
+                ShaderType::GeometryShader => "Geometry Shader",
+                ShaderType::TessControlShader => "Tessellation Control Shader",
+                ShaderType::TessEvaluationShader => "Tessellation Evaluation Shader",
+                ShaderType::ComputeShader => "Compute Shader",
             }
         )
     }
@@ -137,6 +193,8 @@ impl Display for ShaderType {
 pub enum BufferObjectType {
     ArrayBuffer = gl::ARRAY_BUFFER as isize,
     ElementArrayBuffer = gl::ELEMENT_ARRAY_BUFFER as isize,
+    UniformBuffer = gl::UNIFORM_BUFFER as isize,
+    ShaderStorageBuffer = gl::SHADER_STORAGE_BUFFER as isize,
 }
 
 impl From<BufferObjectType> for u32 {
@@ -161,6 +219,7 @@ pub enum DrawType {
     StreamDraw = gl::STREAM_DRAW as isize,
     StaticDraw = gl::STATIC_DRAW as isize,
     DynamicDraw = gl::DYNAMIC_DRAW as isize,
+    DynamicCopy = gl::DYNAMIC_COPY as isize,
 }
 
 impl From<DrawType> for u32 {
@@ -168,3 +227,274 @@ impl From<DrawType> for u32 {
         value as Self
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrimitiveType {
+    Triangles = gl::TRIANGLES as isize,
+    Patches = gl::PATCHES as isize,
+}
+
+impl From<PrimitiveType> for u32 {
+    fn from(value: PrimitiveType) -> Self {
+        value as Self
+    }
+}
+
+/// Set of `glMemoryBarrier` bits, combine them with `|`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryBarrier(u32);
+
+impl MemoryBarrier {
+    pub const SHADER_STORAGE: Self = Self(gl::SHADER_STORAGE_BARRIER_BIT);
+    pub const VERTEX_ATTRIB_ARRAY: Self = Self(gl::VERTEX_ATTRIB_ARRAY_BARRIER_BIT);
+    pub const BUFFER_UPDATE: Self = Self(gl::BUFFER_UPDATE_BARRIER_BIT);
+    pub const TEXTURE_FETCH: Self = Self(gl::TEXTURE_FETCH_BARRIER_BIT);
+    pub const ALL: Self = Self(gl::ALL_BARRIER_BITS);
+}
+
+impl BitOr for MemoryBarrier {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl From<MemoryBarrier> for u32 {
+    fn from(value: MemoryBarrier) -> Self {
+        value.0
+    }
+}
+
+/// Makes writes of previous compute dispatches, from any program, visible
+/// to the accesses described by `barrier`. Barriers are global GL state.
+pub fn memory_barrier(barrier: MemoryBarrier) {
+    unsafe { gl::MemoryBarrier(barrier.into()) };
+}
+
+/// Type of an active program input or uniform as reported by the driver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlslType {
+    Float,
+    Vec2,
+    Vec3,
+    Vec4,
+    Int,
+    IVec2,
+    IVec3,
+    IVec4,
+    UInt,
+    UVec2,
+    UVec3,
+    UVec4,
+    Bool,
+    Mat2,
+    Mat3,
+    Mat4,
+    Sampler2D,
+    Sampler3D,
+    SamplerCube,
+    Sampler2DArray,
+    Sampler2DShadow,
+    Other(u32),
+}
+
+impl GlslType {
+    /// Number of scalar components for scalar and vector types.
+    pub fn components(&self) -> Option<u32> {
+        match self {
+            GlslType::Float | GlslType::Int | GlslType::UInt | GlslType::Bool => Some(1),
+            GlslType::Vec2 | GlslType::IVec2 | GlslType::UVec2 => Some(2),
+            GlslType::Vec3 | GlslType::IVec3 | GlslType::UVec3 => Some(3),
+            GlslType::Vec4 | GlslType::IVec4 | GlslType::UVec4 => Some(4),
+            _ => None,
+        }
+    }
+}
+
+impl From<u32> for GlslType {
+    fn from(value: u32) -> Self {
+        match value {
+            gl::FLOAT => GlslType::Float,
+            gl::FLOAT_VEC2 => GlslType::Vec2,
+            gl::FLOAT_VEC3 => GlslType::Vec3,
+            gl::FLOAT_VEC4 => GlslType::Vec4,
+            gl::INT => GlslType::Int,
+            gl::INT_VEC2 => GlslType::IVec2,
+            gl::INT_VEC3 => GlslType::IVec3,
+            gl::INT_VEC4 => GlslType::IVec4,
+            gl::UNSIGNED_INT => GlslType::UInt,
+            gl::UNSIGNED_INT_VEC2 => GlslType::UVec2,
+            gl::UNSIGNED_INT_VEC3 => GlslType::UVec3,
+            gl::UNSIGNED_INT_VEC4 => GlslType::UVec4,
+            gl::BOOL => GlslType::Bool,
+            gl::FLOAT_MAT2 => GlslType::Mat2,
+            gl::FLOAT_MAT3 => GlslType::Mat3,
+            gl::FLOAT_MAT4 => GlslType::Mat4,
+            gl::SAMPLER_2D => GlslType::Sampler2D,
+            gl::SAMPLER_3D => GlslType::Sampler3D,
+            gl::SAMPLER_CUBE => GlslType::SamplerCube,
+            gl::SAMPLER_2D_ARRAY => GlslType::Sampler2DArray,
+            gl::SAMPLER_2D_SHADOW => GlslType::Sampler2DShadow,
+            other => GlslType::Other(other),
+        }
+    }
+}
+
+impl Display for GlslType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GlslType::Other(ty) => write!(f, "0x{ty:x}"),
+            ty => write!(f, "{}", format!("{ty:?}").to_lowercase()),
+        }
+    }
+}
+
+/// How image data is stored on the GPU and described to `glTexImage*`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelFormat {
+    pub internal_format: u32,
+    pub format: u32,
+    pub data_type: u32,
+    /// Channel swizzle applied when sampling, used to expand
+    /// single and two channel luminance images to gray.
+    pub swizzle: Option<[u32; 4]>,
+}
+
+impl PixelFormat {
+    pub const RGBA8: Self = Self::new(gl::RGBA8, gl::RGBA, gl::UNSIGNED_BYTE);
+    pub const SRGB8_ALPHA8: Self = Self::new(gl::SRGB8_ALPHA8, gl::RGBA, gl::UNSIGNED_BYTE);
+    pub const RG16F: Self = Self::new(gl::RG16F, gl::RG, gl::HALF_FLOAT);
+    pub const RGBA16F: Self = Self::new(gl::RGBA16F, gl::RGBA, gl::HALF_FLOAT);
+    pub const RGBA32F: Self = Self::new(gl::RGBA32F, gl::RGBA, gl::FLOAT);
+    pub const DEPTH24: Self = Self::new(gl::DEPTH_COMPONENT24, gl::DEPTH_COMPONENT, gl::FLOAT);
+    pub const DEPTH32F: Self = Self::new(gl::DEPTH_COMPONENT32F, gl::DEPTH_COMPONENT, gl::FLOAT);
+    pub const DEPTH24_STENCIL8: Self = Self::new(
+        gl::DEPTH24_STENCIL8,
+        gl::DEPTH_STENCIL,
+        gl::UNSIGNED_INT_24_8,
+    );
+    pub const STENCIL8: Self = Self::new(gl::STENCIL_INDEX8, gl::STENCIL_INDEX, gl::UNSIGNED_BYTE);
+
+    pub const fn new(internal_format: u32, format: u32, data_type: u32) -> Self {
+        Self {
+            internal_format,
+            format,
+            data_type,
+            swizzle: None,
+        }
+    }
+
+    /// `None` for color types the engine can't upload directly.
+    pub fn from_color_type(color: image::ColorType) -> Option<Self> {
+        use image::ColorType;
+
+        const LUMINANCE: [u32; 4] = [gl::RED, gl::RED, gl::RED, gl::ONE];
+        const LUMINANCE_ALPHA: [u32; 4] = [gl::RED, gl::RED, gl::RED, gl::GREEN];
+
+        let format = match color {
+            ColorType::L8 => Self {
+                swizzle: Some(LUMINANCE),
+                ..Self::new(gl::R8, gl::RED, gl::UNSIGNED_BYTE)
+            },
+            ColorType::La8 => Self {
+                swizzle: Some(LUMINANCE_ALPHA),
+                ..Self::new(gl::RG8, gl::RG, gl::UNSIGNED_BYTE)
+            },
+            ColorType::Rgb8 => Self::new(gl::RGB8, gl::RGB, gl::UNSIGNED_BYTE),
+            ColorType::Rgba8 => Self::new(gl::RGBA8, gl::RGBA, gl::UNSIGNED_BYTE),
+            ColorType::L16 => Self {
+                swizzle: Some(LUMINANCE),
+                ..Self::new(gl::R16, gl::RED, gl::UNSIGNED_SHORT)
+            },
+            ColorType::La16 => Self {
+                swizzle: Some(LUMINANCE_ALPHA),
+                ..Self::new(gl::RG16, gl::RG, gl::UNSIGNED_SHORT)
+            },
+            ColorType::Rgb16 => Self::new(gl::RGB16, gl::RGB, gl::UNSIGNED_SHORT),
+            ColorType::Rgba16 => Self::new(gl::RGBA16, gl::RGBA, gl::UNSIGNED_SHORT),
+            ColorType::Rgb32F => Self::new(gl::RGB32F, gl::RGB, gl::FLOAT),
+            ColorType::Rgba32F => Self::new(gl::RGBA32F, gl::RGBA, gl::FLOAT),
+            _ => return None,
+        };
+
+        Some(format)
+    }
+
+    pub fn bytes_per_pixel(&self) -> usize {
+        if self.data_type == gl::UNSIGNED_INT_24_8 {
+            return 4;
+        }
+
+        let channels = match self.format {
+            gl::RED | gl::RED_INTEGER | gl::DEPTH_COMPONENT | gl::STENCIL_INDEX => 1,
+            gl::RG | gl::RG_INTEGER => 2,
+            gl::RGB | gl::BGR | gl::RGB_INTEGER => 3,
+            _ => 4,
+        };
+        let size = match self.data_type {
+            gl::UNSIGNED_BYTE | gl::BYTE => 1,
+            gl::UNSIGNED_SHORT | gl::SHORT | gl::HALF_FLOAT => 2,
+            _ => 4,
+        };
+        channels * size
+    }
+
+    /// Switches 8-bit color formats to their sRGB counterparts.
+    pub fn with_color_space(self, color_space: ColorSpace) -> Self {
+        let internal_format = match (color_space, self.internal_format) {
+            (ColorSpace::Srgb, gl::RGB8) => gl::SRGB8,
+            (ColorSpace::Srgb, gl::RGBA8) => gl::SRGB8_ALPHA8,
+            (_, internal_format) => internal_format,
+        };
+
+        Self {
+            internal_format,
+            ..self
+        }
+    }
+}
+
+/// Encoding of the color values stored in a texture.
+///
+/// The renderer does its math in linear space: `Srgb` textures are decoded by the
+/// sampler, and the default framebuffer encodes the linear result back to sRGB on write.
+/// Color art (albedo, UI) is authored in sRGB, while normal, roughness and other data maps
+/// must stay `Linear`. Only 8-bit RGB(A) images have an sRGB storage format; other
+/// formats are always sampled as linear.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorSpace {
+    #[default]
+    Srgb,
+    Linear,
+}
+
+/// Largest `GL_UNPACK_ALIGNMENT` rows of `row_bytes` are guaranteed to satisfy.
+pub fn unpack_alignment(row_bytes: usize) -> i32 {
+    [8, 4, 2]
+        .into_iter()
+        // Powers of two, so masking the low bits tests divisibility.
+        .find(|alignment| row_bytes & (alignment - 1) == 0)
+        .unwrap_or(1) as _
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unpack_alignment_is_largest_dividing_power_of_two() {
+        assert_eq!(unpack_alignment(3), 1);
+        assert_eq!(unpack_alignment(6), 2);
+        assert_eq!(unpack_alignment(12), 4);
+        assert_eq!(unpack_alignment(24), 8);
+        assert_eq!(unpack_alignment(1024), 8);
+    }
+
+    #[test]
+    fn texture_unit_limit_keeps_the_first_samplers() {
+        MAX_TEXTURE_UNITS.store(2, Ordering::Relaxed);
+        let textures = TextureUnit::limit([("a", 0), ("b", 1), ("c", 2)]);
+        assert_eq!(textures, [("a", 0), ("b", 1)]);
+    }
+}
@@ -0,0 +1,103 @@
+use std::{marker::PhantomData, mem::size_of, slice};
+
+use engine_math::Matrix;
+
+use super::{
+    buffer_object::BufferObject,
+    types::{Mat4, Vec3, Vec4},
+};
+
+/// Binding point of the per-frame [`CameraBlock`].
+pub const CAMERA_BLOCK_BINDING: u32 = 0;
+/// Binding point used for the block of the material being drawn.
+pub const MATERIAL_BLOCK_BINDING: u32 = 1;
+/// Binding point of the per-frame [`LightsBlock`](super::lighting::LightsBlock).
+pub const LIGHTS_BLOCK_BINDING: u32 = 2;
+/// Binding point of the per-frame [`ShadowsBlock`](super::shadows::ShadowsBlock).
+pub const SHADOWS_BLOCK_BINDING: u32 = 3;
+
+/// Rust side of a GLSL `layout(std140) uniform` block.
+///
+/// Implementors must lay their fields out by std140 rules: `vec3` members and array
+/// elements take 16 bytes (store them as `Vec4`), matrices are column-major.
+///
+/// # Safety
+///
+/// [`Self::as_bytes`] reads every byte of the value, so implementors must be
+/// `#[repr(C)]` and contain no implicit padding, which would be uninitialized.
+/// Spell padding out as explicit fields instead.
+pub unsafe trait UniformBlock: Copy {
+    /// Block name as declared in GLSL.
+    const NAME: &'static str;
+
+    fn as_bytes(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts((self as *const Self).cast(), size_of::<Self>()) }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CameraBlock {
+    pub projection: Mat4,
+    pub view: Mat4,
+    pub position: Vec4,
+}
+
+impl CameraBlock {
+    pub fn new(projection: Mat4, view: Mat4, position: Vec3) -> Self {
+        Self {
+            projection: projection.transpose(),
+            view: view.transpose(),
+            position: Vec4::new(position.x, position.y, position.z, 1.),
+        }
+    }
+}
+
+unsafe impl UniformBlock for CameraBlock {
+    const NAME: &'static str = "Camera";
+}
+
+#[derive(Debug, Clone)]
+pub struct UniformBuffer<T: UniformBlock> {
+    bo: BufferObject,
+    binding: u32,
+    _block: PhantomData<T>,
+}
+
+impl<T: UniformBlock> UniformBuffer<T> {
+    pub fn new(binding: u32) -> Self {
+        let bo = BufferObject::uniform_buffer_object();
+        bo.bind();
+        unsafe {
+            gl::BufferData(
+                bo.bo_type.into(),
+                size_of::<T>() as _,
+                std::ptr::null(),
+                bo.draw_type.into(),
+            );
+        }
+        bo.bind_base(binding);
+
+        Self {
+            bo,
+            binding,
+            _block: PhantomData,
+        }
+    }
+
+    pub fn binding(&self) -> u32 {
+        self.binding
+    }
+
+    pub fn update(&self, block: &T) {
+        self.bo.bind();
+        unsafe {
+            gl::BufferSubData(
+                self.bo.bo_type.into(),
+                0,
+                size_of::<T>() as _,
+                block.as_bytes().as_ptr().cast(),
+            );
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use gl::types::*;
 
-use super::{attribute_pointer::AttributePointers, buffer_object::*};
+use super::{attribute_pointer::AttributePointers, buffer_object::*, types::PrimitiveType};
 
 #[derive(Debug, Clone)]
 pub struct VertexArray {
@@ -25,6 +25,10 @@ impl VertexArray {
         }
     }
 
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
     pub fn upload_data<T, U>(&mut self, vertex_data: &[T], indices_data: &[U]) {
         self.associate_vertices = indices_data.len() as _;
         self.vertex_bo.bind();
@@ -45,16 +49,33 @@ impl VertexArray {
                 vap.ptr,
             );
             gl::EnableVertexAttribArray(vap.location);
+            gl::VertexAttribDivisor(vap.location, vap.divisor);
         }
     }
 
     pub fn draw(&self) {
+        self.draw_primitive(PrimitiveType::Triangles);
+    }
+
+    pub fn draw_primitive(&self, primitive: PrimitiveType) {
         unsafe {
             gl::DrawElements(
-                gl::TRIANGLES,
+                primitive.into(),
+                self.associate_vertices,
+                gl::UNSIGNED_INT,
+                std::ptr::null(),
+            );
+        }
+    }
+
+    pub fn draw_instanced(&self, primitive: PrimitiveType, instances: usize) {
+        unsafe {
+            gl::DrawElementsInstanced(
+                primitive.into(),
                 self.associate_vertices,
                 gl::UNSIGNED_INT,
                 std::ptr::null(),
+                instances as _,
             );
         }
     }